@@ -0,0 +1,146 @@
+use std::future::Future;
+use std::time::Instant;
+
+use chrono::NaiveDateTime;
+use tokio::sync::mpsc;
+
+use crate::db::dbutils::TaskPage;
+use crate::db::error::DbError;
+use crate::db::model::{DexVolumeTask, MmVolumeTask};
+use crate::db::schema::TaskTable;
+use crate::environment::Connection;
+use crate::form::Form;
+use crate::view::Query;
+use crate::RowAction;
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// What a background action produced.
+pub enum Outcome {
+    /// A row was updated; holds its id.
+    Saved(u64),
+    /// A row was inserted or copied; holds the new id.
+    Created(u64),
+    /// Where the query of a view puts row `id`, `None` when its filter leaves the row out.
+    Located {
+        id: u64,
+        offset: Option<u64>,
+    },
+    Deleted,
+    /// A page, with the query it was fetched for.
+    MmLoaded(Query, TaskPage<MmVolumeTask>),
+    DexLoaded(Query, TaskPage<DexVolumeTask>),
+    /// A database of another environment is reachable.
+    Connected(Connection),
+}
+
+/// Sent back over the channel when a background action ends.
+pub struct Finished {
+    pub ticket: u64,
+    pub outcome: Result<Outcome, DbError>,
+}
+
+/// Popup to reopen when an action fails, so the user can fix and resend it.
+pub enum Retry {
+    None,
+    Cell {
+        key_value: String,
+        version: Option<NaiveDateTime>,
+        column: String,
+        original: String,
+        input: String,
+    },
+    Form(Form),
+    Confirm(RowAction, String),
+}
+
+/// An action still running.
+pub struct Pending {
+    ticket: u64,
+    pub table: TaskTable,
+    /// Row the action works on, marked in the table until it ends.
+    pub row: Option<u64>,
+    pub label: &'static str,
+    pub retry: Retry,
+}
+
+/// Database work started from the UI, run as tokio tasks so drawing and input never wait on MySQL.
+pub struct Actions {
+    tx: mpsc::Sender<Finished>,
+    next_ticket: u64,
+    pending: Vec<Pending>,
+    started: Instant,
+}
+
+impl Actions {
+    pub fn new(tx: mpsc::Sender<Finished>) -> Self {
+        Self {
+            tx,
+            next_ticket: 0,
+            pending: vec![],
+            started: Instant::now(),
+        }
+    }
+
+    /// Run `action` in the background; its result comes back as a `Finished` with the same ticket.
+    pub fn spawn<F>(
+        &mut self,
+        table: TaskTable,
+        row: Option<u64>,
+        label: &'static str,
+        retry: Retry,
+        action: F,
+    ) where
+        F: Future<Output = Result<Outcome, DbError>> + Send + 'static,
+    {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.pending.push(Pending {
+            ticket,
+            table,
+            row,
+            label,
+            retry,
+        });
+
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let outcome = action.await;
+            let _ = tx.send(Finished { ticket, outcome }).await;
+        });
+    }
+
+    /// Stop tracking the action with `ticket` and hand it back.
+    pub fn finish(&mut self, ticket: u64) -> Option<Pending> {
+        let index = self
+            .pending
+            .iter()
+            .position(|pending| pending.ticket == ticket)?;
+        Some(self.pending.remove(index))
+    }
+
+    /// Forget every running action; their results are ignored when they come back.
+    pub fn discard_all(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Label of the action running on `row`, if any.
+    pub fn row_label(&self, table: TaskTable, row: u64) -> Option<&'static str> {
+        self.pending
+            .iter()
+            .find(|pending| pending.table == table && pending.row == Some(row))
+            .map(|pending| pending.label)
+    }
+
+    pub fn has_rows(&self, table: TaskTable) -> bool {
+        self.pending
+            .iter()
+            .any(|pending| pending.table == table && pending.row.is_some())
+    }
+
+    /// Current spinner frame.
+    pub fn spinner(&self) -> &'static str {
+        let frame = self.started.elapsed().as_millis() / 100;
+        SPINNER[frame as usize % SPINNER.len()]
+    }
+}
@@ -0,0 +1,358 @@
+use std::path::PathBuf;
+
+use clap::{command, value_parser, Arg, ArgAction, ArgMatches, Command};
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::dbutils::{Database, Expected, Page, Sort, TaskPage};
+use crate::db::error::DbError;
+use crate::db::filter::Filter;
+use crate::db::model::{DexVolumeTask, MmVolumeTask};
+use crate::db::schema::TaskTable;
+use crate::display::TaskRow;
+use crate::error::AppError;
+
+/// Arguments of the tool; without a subcommand it starts the TUI.
+pub fn command() -> Command {
+    command!()
+        .about("Manage MM and DEX volume tasks; starts the TUI without a subcommand")
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .global(true)
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .help(
+                    "Config file, instead of searching /opt/xtool and the XDG config directories",
+                ),
+        )
+        .arg(
+            Arg::new("log-config")
+                .long("log-config")
+                .global(true)
+                .value_name("PATH")
+                .value_parser(value_parser!(PathBuf))
+                .help("log4rs config file, instead of searching the same places as the config"),
+        )
+        .arg(
+            Arg::new("env")
+                .long("env")
+                .global(true)
+                .value_name("NAME")
+                .help("Environment from the config to connect to, instead of default_env"),
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Print JSON instead of a table"),
+        )
+        .subcommand(table_command("mm", "MM volume tasks"))
+        .subcommand(table_command("dex", "DEX volume tasks"))
+}
+
+fn table_command(name: &'static str, about: &'static str) -> Command {
+    let id = || {
+        Arg::new("id")
+            .required(true)
+            .value_parser(value_parser!(u64))
+            .help("Task id")
+    };
+
+    Command::new(name)
+        .about(about)
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List tasks matching a filter")
+                .arg(
+                    Arg::new("filter")
+                        .long("filter")
+                        .short('f')
+                        .help("Filter as typed in the search box, e.g. 'do_status=1 remark~whale'"),
+                )
+                .arg(Arg::new("sort").long("sort").help("Column to order by"))
+                .arg(
+                    Arg::new("desc")
+                        .long("desc")
+                        .action(ArgAction::SetTrue)
+                        .help("Order from the largest value"),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_parser(value_parser!(u64).range(1..))
+                        .default_value("100")
+                        .help("Rows to print at most"),
+                )
+                .arg(
+                    Arg::new("offset")
+                        .long("offset")
+                        .value_parser(value_parser!(u64))
+                        .default_value("0")
+                        .help("Matching rows to skip"),
+                ),
+        )
+        .subcommand(Command::new("show").about("Print one task").arg(id()))
+        .subcommand(
+            Command::new("set")
+                .about("Change columns of one task")
+                .arg(id())
+                .arg(
+                    Arg::new("values")
+                        .required(true)
+                        .num_args(1..)
+                        .value_name("COLUMN=VALUE")
+                        .help("Columns to write; an empty value clears a nullable column"),
+                ),
+        )
+        .subcommand(
+            Command::new("copy")
+                .about("Copy a task as a stopped one")
+                .arg(id()),
+        )
+        .subcommand(Command::new("delete").about("Delete a task").arg(id()))
+}
+
+/// A task model the subcommands can read pages and single rows of.
+trait Task: TaskRow + Serialize + Sized {
+    const TABLE: TaskTable;
+
+    async fn page(
+        db: &Database,
+        filter: &Filter,
+        sort: Option<Sort>,
+        page: Page,
+    ) -> Result<TaskPage<Self>, DbError>;
+
+    async fn by_id(db: &Database, id: u64) -> Result<Option<Self>, DbError>;
+}
+
+impl Task for MmVolumeTask {
+    const TABLE: TaskTable = TaskTable::Mm;
+
+    async fn page(
+        db: &Database,
+        filter: &Filter,
+        sort: Option<Sort>,
+        page: Page,
+    ) -> Result<TaskPage<Self>, DbError> {
+        db.get_mm_volume_task_page(filter, sort, page, None).await
+    }
+
+    async fn by_id(db: &Database, id: u64) -> Result<Option<Self>, DbError> {
+        db.get_mm_volume_task_by_id(id).await
+    }
+}
+
+impl Task for DexVolumeTask {
+    const TABLE: TaskTable = TaskTable::Dex;
+
+    async fn page(
+        db: &Database,
+        filter: &Filter,
+        sort: Option<Sort>,
+        page: Page,
+    ) -> Result<TaskPage<Self>, DbError> {
+        db.get_dex_volume_task_page(filter, sort, page, None).await
+    }
+
+    async fn by_id(db: &Database, id: u64) -> Result<Option<Self>, DbError> {
+        db.get_dex_volume_task_by_id(id).await
+    }
+}
+
+/// Run the subcommand in `matches`, printing its result to stdout.
+pub async fn run(db: &Database, matches: &ArgMatches) -> Result<(), AppError> {
+    let json = matches.get_flag("json");
+    match matches.subcommand() {
+        Some(("mm", matches)) => run_table::<MmVolumeTask>(db, matches, json).await,
+        Some(("dex", matches)) => run_table::<DexVolumeTask>(db, matches, json).await,
+        _ => Ok(()),
+    }
+}
+
+async fn run_table<T: Task>(
+    db: &Database,
+    matches: &ArgMatches,
+    json: bool,
+) -> Result<(), AppError> {
+    let table = T::TABLE;
+    let schema = table.schema();
+
+    match matches.subcommand() {
+        Some(("list", args)) => {
+            let filter = args.get_one::<String>("filter").map_or("", String::as_str);
+            let filter = Filter::parse(schema, filter)?;
+            let sort = match args.get_one::<String>("sort") {
+                Some(name) => {
+                    let column = schema.column(name).ok_or_else(|| DbError::UnknownColumn {
+                        table: schema.table,
+                        column: name.to_string(),
+                    })?;
+                    let sort = Sort::ascending(column.name);
+                    Some(if args.get_flag("desc") {
+                        sort.reversed()
+                    } else {
+                        sort
+                    })
+                }
+                None => None,
+            };
+            let page = Page {
+                offset: *args.get_one::<u64>("offset").unwrap_or(&0),
+                limit: *args.get_one::<u64>("limit").unwrap_or(&100),
+            };
+
+            print_page(&T::page(db, &filter, sort, page).await?, json)
+        }
+        Some(("show", args)) => print_row(&find::<T>(db, id(args)).await?, json),
+        Some(("set", args)) => {
+            let id = id(args);
+            let mut values = vec![];
+            for pair in args.get_many::<String>("values").into_iter().flatten() {
+                let (column, value) = pair.split_once('=').ok_or_else(|| {
+                    AppError::InvalidArgument(format!("expected COLUMN=VALUE, got `{}`", pair))
+                })?;
+                values.push((column.trim(), value));
+            }
+
+            // refused like an edit in the TUI if the row changes between reading and writing
+            let row = find::<T>(db, id).await?;
+            let current: Vec<(&str, String)> =
+                T::COLUMNS.iter().copied().zip(row.values()).collect();
+            let originals: Vec<(&str, &str)> = current
+                .iter()
+                .filter(|(name, _)| values.iter().any(|(column, _)| column == name))
+                .map(|(name, value)| (*name, value.as_str()))
+                .collect();
+            db.update_columns(
+                table,
+                "id",
+                &id.to_string(),
+                &values,
+                Expected {
+                    version: row.version(),
+                    originals: &originals,
+                },
+            )
+            .await?;
+            print_row(&find::<T>(db, id).await?, json)
+        }
+        Some(("copy", args)) => {
+            let id = id(args);
+            let copy = db.copy_record(table, &id.to_string()).await?;
+            if json {
+                println!("{}", serde_json::json!({ "id": copy, "copied_from": id }));
+            } else {
+                println!("copied #{} to #{}", id, copy);
+            }
+            Ok(())
+        }
+        Some(("delete", args)) => {
+            let id = id(args);
+            if !db.delete_record(table, &id.to_string()).await? {
+                return Err(DbError::NotFound {
+                    table: schema.table,
+                    key: id.to_string(),
+                }
+                .into());
+            }
+            if json {
+                println!("{}", serde_json::json!({ "deleted": id }));
+            } else {
+                println!("deleted #{}", id);
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn id(args: &ArgMatches) -> u64 {
+    // required and parsed as u64 by clap
+    *args.get_one::<u64>("id").unwrap_or(&0)
+}
+
+/// The task with `id`.
+async fn find<T: Task>(db: &Database, id: u64) -> Result<T, AppError> {
+    T::by_id(db, id).await?.ok_or_else(|| {
+        DbError::NotFound {
+            table: T::TABLE.schema().table,
+            key: id.to_string(),
+        }
+        .into()
+    })
+}
+
+fn print_page<T: TaskRow + Serialize>(page: &TaskPage<T>, json: bool) -> Result<(), AppError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(&page.items)?);
+        return Ok(());
+    }
+
+    print_table(&page.items);
+    let first = if page.items.is_empty() {
+        0
+    } else {
+        page.page.offset + 1
+    };
+    // keep stdout to the rows, so it can be piped
+    eprintln!(
+        "rows {}-{} of {}",
+        first,
+        page.page.offset + page.items.len() as u64,
+        page.total
+    );
+    Ok(())
+}
+
+fn print_row<T: TaskRow + Serialize>(row: &T, json: bool) -> Result<(), AppError> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(row)?);
+        return Ok(());
+    }
+
+    let width = T::COLUMNS
+        .iter()
+        .map(|name| name.width())
+        .max()
+        .unwrap_or(0);
+    for (name, cell) in T::COLUMNS.iter().zip(row.cells()) {
+        println!("{:>width$} : {}", name, cell);
+    }
+    Ok(())
+}
+
+/// Rows under a header, columns padded to their widest cell.
+fn print_table<T: TaskRow>(items: &[T]) {
+    let rows: Vec<Vec<String>> = items.iter().map(TaskRow::cells).collect();
+    let widths: Vec<usize> = T::COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            rows.iter()
+                .map(|cells| cells[i].width())
+                .max()
+                .unwrap_or(0)
+                .max(name.width())
+        })
+        .collect();
+
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.width())))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", line(T::COLUMNS.to_vec()));
+    for cells in &rows {
+        println!("{}", line(cells.iter().map(String::as_str).collect()));
+    }
+}
@@ -0,0 +1,501 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::schema::TaskTable;
+use crate::error::AppError;
+
+/// Where deployments have always kept the config, searched before the XDG locations.
+const SYSTEM_DIR: &str = "/opt/xtool";
+
+pub const CONFIG_FILE: &str = "config.toml";
+pub const LOG_CONFIG_FILE: &str = "log4rs.yaml";
+
+/// Prefix of the environment variables overriding config fields.
+const ENV_PREFIX: &str = "XTOOL_DB_";
+
+/// Name of the database set at the top level of the config.
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// One database the tool can work on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Environment {
+    pub db_server: String,
+    pub db_port: u16,
+    pub db_user: String,
+    pub db_password: String,
+    pub db_name: String,
+    /// Shown in red, so edits there are not made by mistake.
+    pub production: bool,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Self {
+            db_server: "http://localhost".to_string(),
+            db_port: 3306,
+            db_user: "root".to_string(),
+            db_password: "password".to_string(),
+            db_name: "db".to_string(),
+            production: false,
+        }
+    }
+}
+
+impl Environment {
+    /// Replace the database fields set by `XTOOL_DB_*` variables; only done for `default`.
+    fn apply_env(&mut self) -> Result<(), AppError> {
+        env_override(&mut self.db_server, "SERVER")?;
+        env_override(&mut self.db_port, "PORT")?;
+        env_override(&mut self.db_user, "USER")?;
+        env_override(&mut self.db_password, "PASSWORD")?;
+        env_override(&mut self.db_name, "NAME")?;
+        env_override(&mut self.production, "PRODUCTION")?;
+        Ok(())
+    }
+
+    pub fn database_url(&self) -> String {
+        format!(
+            "mysql://{}:{}@{}:{}/{}",
+            self.db_user, self.db_password, self.db_server, self.db_port, self.db_name
+        )
+    }
+}
+
+/// An `[environments.<name>]` table. Fields left out are taken from the
+/// `default` environment; unknown ones are refused, so a typo is not mistaken
+/// for a field left out.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct NamedEnvironment {
+    pub db_server: Option<String>,
+    pub db_port: Option<u16>,
+    pub db_user: Option<String>,
+    pub db_password: Option<String>,
+    pub db_name: Option<String>,
+    pub production: Option<bool>,
+}
+
+impl NamedEnvironment {
+    /// The settings of this environment, with the fields left out taken from `default`.
+    fn over(&self, default: &Environment) -> Environment {
+        Environment {
+            db_server: self.db_server.clone().unwrap_or(default.db_server.clone()),
+            db_port: self.db_port.unwrap_or(default.db_port),
+            db_user: self.db_user.clone().unwrap_or(default.db_user.clone()),
+            db_password: self
+                .db_password
+                .clone()
+                .unwrap_or(default.db_password.clone()),
+            db_name: self.db_name.clone().unwrap_or(default.db_name.clone()),
+            production: self.production.unwrap_or(default.production),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(default)]
+pub struct Config {
+    /// The `default` environment, kept at the top level as before environments existed.
+    #[serde(flatten)]
+    pub db: Environment,
+    pub enable_logging: bool,
+    /// Seconds between background refreshes of the MM view.
+    pub mm_refresh_secs: u64,
+    /// Seconds between background refreshes of the DEX view.
+    pub dex_refresh_secs: u64,
+    /// Environment to connect to when `--env` is not given.
+    pub default_env: Option<String>,
+    /// Further databases, from `[environments.<name>]` tables.
+    pub environments: BTreeMap<String, NamedEnvironment>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            db: Environment::default(),
+            enable_logging: true,
+            mm_refresh_secs: 10,
+            dex_refresh_secs: 10,
+            default_env: None,
+            environments: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file at `path`, then apply the environment overrides.
+    pub fn load(path: &Path) -> Result<Self, AppError> {
+        let mut config: Config = confy::load_path(path)?;
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Replace fields set by `XTOOL_DB_*` variables, named after the field
+    /// without its `db_` prefix: `XTOOL_DB_SERVER`, `XTOOL_DB_PORT`,
+    /// `XTOOL_DB_ENABLE_LOGGING`, `XTOOL_DB_MM_REFRESH_SECS` and so on.
+    /// The database fields only apply to the `default` environment, so a named
+    /// one is never shown over another's database.
+    fn apply_env(&mut self) -> Result<(), AppError> {
+        env_override(&mut self.enable_logging, "ENABLE_LOGGING")?;
+        env_override(&mut self.mm_refresh_secs, "MM_REFRESH_SECS")?;
+        env_override(&mut self.dex_refresh_secs, "DEX_REFRESH_SECS")?;
+        if let Ok(name) = env::var(format!("{}DEFAULT_ENV", ENV_PREFIX)) {
+            self.default_env = Some(name);
+        }
+        Ok(())
+    }
+
+    /// `default` followed by the named environments.
+    pub fn environment_names(&self) -> Vec<String> {
+        let mut names = vec![DEFAULT_ENVIRONMENT.to_string()];
+        names.extend(
+            self.environments
+                .keys()
+                .filter(|name| *name != DEFAULT_ENVIRONMENT)
+                .cloned(),
+        );
+        names
+    }
+
+    /// Name of the environment to start on: `requested`, else `default_env`, else `default`.
+    pub fn start_environment(&self, requested: Option<&str>) -> String {
+        requested
+            .or(self.default_env.as_deref())
+            .unwrap_or(DEFAULT_ENVIRONMENT)
+            .to_string()
+    }
+
+    /// The database settings of environment `name`, with the overrides applied to `default`.
+    pub fn environment(&self, name: &str) -> Result<Environment, AppError> {
+        let mut environment = match self.environments.get(name) {
+            Some(environment) => environment.over(&self.db),
+            None if name == DEFAULT_ENVIRONMENT => self.db.clone(),
+            None => {
+                return Err(AppError::UnknownEnvironment {
+                    name: name.to_string(),
+                    known: self.environment_names(),
+                })
+            }
+        };
+        if name == DEFAULT_ENVIRONMENT {
+            environment.apply_env()?;
+        }
+        Ok(environment)
+    }
+
+    /// Wait between refreshes of `table`, at least a second.
+    pub fn refresh_interval(&self, table: TaskTable) -> Duration {
+        let secs = match table {
+            TaskTable::Mm => self.mm_refresh_secs,
+            TaskTable::Dex => self.dex_refresh_secs,
+        };
+        Duration::from_secs(secs.max(1))
+    }
+}
+
+fn env_override<T: FromStr>(field: &mut T, name: &str) -> Result<(), AppError> {
+    let name = format!("{}{}", ENV_PREFIX, name);
+    if let Ok(value) = env::var(&name) {
+        *field = value
+            .parse()
+            .map_err(|_| AppError::InvalidEnv { name, value })?;
+    }
+    Ok(())
+}
+
+/// The file named `file_name`: `explicit` if given, else the first that exists of
+/// `/opt/xtool`, `$XDG_CONFIG_HOME/xtool` and each of `$XDG_CONFIG_DIRS` with `/xtool`.
+///
+/// The error lists every path looked at.
+pub fn find(explicit: Option<&Path>, file_name: &str) -> Result<PathBuf, AppError> {
+    let searched = match explicit {
+        Some(path) => vec![path.to_path_buf()],
+        None => search_dirs()
+            .into_iter()
+            .map(|dir| dir.join(file_name))
+            .collect(),
+    };
+
+    searched
+        .iter()
+        .find(|path| path.is_file())
+        .cloned()
+        .ok_or_else(|| AppError::MissingFile {
+            file_name: file_name.to_string(),
+            searched,
+        })
+}
+
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(SYSTEM_DIR)];
+
+    // the XDG defaults apply when a variable is unset or empty
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    dirs.extend(config_home.map(|dir| dir.join("xtool")));
+
+    let config_dirs = env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".to_string());
+    dirs.extend(
+        config_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| Path::new(dir).join("xtool")),
+    );
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    /// Tests setting variables of the process take turns.
+    static ENV: Mutex<()> = Mutex::new(());
+
+    /// Run `test` with `vars` set, or removed when `None`, then put them back.
+    fn with_env(vars: &[(&str, Option<&str>)], test: impl FnOnce()) {
+        let _turn = ENV.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let saved: Vec<_> = vars
+            .iter()
+            .map(|(name, _)| (*name, env::var_os(name)))
+            .collect();
+        for (name, value) in vars {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+        test();
+        for (name, value) in saved {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+
+    #[test]
+    fn explicit_path_wins_over_the_search() {
+        let dir = env::temp_dir().join(format!("xtool-find-{}", std::process::id()));
+        fs::create_dir_all(dir.join("xtool")).unwrap();
+        fs::write(dir.join("xtool").join(CONFIG_FILE), "").unwrap();
+        let explicit = dir.join("other.toml");
+        fs::write(&explicit, "").unwrap();
+        let missing = dir.join("missing.toml");
+
+        with_env(&[("XDG_CONFIG_HOME", dir.to_str())], || {
+            assert_eq!(find(Some(&explicit), CONFIG_FILE).unwrap(), explicit);
+            // a missing explicit file is an error, not a reason to search
+            match find(Some(&missing), CONFIG_FILE) {
+                Err(AppError::MissingFile { searched, .. }) => assert_eq!(searched, [missing]),
+                other => panic!("expected a missing file, got {:?}", other),
+            }
+        });
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn empty_xdg_variables_fall_back_like_unset_ones() {
+        let expected = [
+            PathBuf::from(SYSTEM_DIR),
+            PathBuf::from("/home/op/.config/xtool"),
+            PathBuf::from("/etc/xdg/xtool"),
+        ];
+        let unset = [
+            ("HOME", Some("/home/op")),
+            ("XDG_CONFIG_HOME", None),
+            ("XDG_CONFIG_DIRS", None),
+        ];
+        with_env(&unset, || assert_eq!(search_dirs(), expected));
+        let empty = [
+            ("HOME", Some("/home/op")),
+            ("XDG_CONFIG_HOME", Some("")),
+            ("XDG_CONFIG_DIRS", Some("")),
+        ];
+        with_env(&empty, || assert_eq!(search_dirs(), expected));
+    }
+
+    #[test]
+    fn every_config_dir_is_searched_in_order() {
+        let vars = [
+            ("XDG_CONFIG_HOME", Some("/nowhere/home")),
+            ("XDG_CONFIG_DIRS", Some("/nowhere/a::/nowhere/b")),
+        ];
+        with_env(&vars, || {
+            let searched = [
+                PathBuf::from(SYSTEM_DIR).join("absent.toml"),
+                PathBuf::from("/nowhere/home/xtool/absent.toml"),
+                PathBuf::from("/nowhere/a/xtool/absent.toml"),
+                PathBuf::from("/nowhere/b/xtool/absent.toml"),
+            ];
+            let error = find(None, "absent.toml").unwrap_err();
+            for path in &searched {
+                assert!(error.to_string().contains(path.to_str().unwrap()));
+            }
+            match error {
+                AppError::MissingFile {
+                    searched: listed, ..
+                } => assert_eq!(listed, searched),
+                other => panic!("expected a missing file, got {:?}", other),
+            }
+        });
+    }
+
+    #[test]
+    fn overrides_replace_fields_and_reject_bad_values() {
+        with_env(
+            &[
+                ("XTOOL_DB_SERVER", Some("db.internal")),
+                ("XTOOL_DB_PORT", Some("3307")),
+            ],
+            || {
+                let mut environment = Environment::default();
+                environment.apply_env().unwrap();
+                assert_eq!(environment.db_server, "db.internal");
+                assert_eq!(environment.db_port, 3307);
+            },
+        );
+        with_env(
+            &[("XTOOL_DB_PORT", Some("mysql"))],
+            || match Environment::default().apply_env() {
+                Err(AppError::InvalidEnv { name, value }) => {
+                    assert_eq!(name, "XTOOL_DB_PORT");
+                    assert_eq!(value, "mysql");
+                }
+                other => panic!("expected an invalid variable, got {:?}", other),
+            },
+        );
+    }
+
+    fn load(text: &str) -> Result<Config, AppError> {
+        let path = env::temp_dir().join(format!(
+            "xtool-config-{}-{}.toml",
+            std::process::id(),
+            text.len()
+        ));
+        fs::write(&path, text).unwrap();
+        let config = Config::load(&path);
+        fs::remove_file(path).unwrap();
+        config
+    }
+
+    #[test]
+    fn named_environments_take_left_out_fields_from_default() {
+        let config = load(
+            r#"
+            db_server = "db.local"
+            db_user = "ops"
+            db_password = "secret"
+            db_name = "moonpump"
+
+            [environments.staging]
+            db_server = "staging.db"
+            db_name = "moonpump_staging"
+            "#,
+        )
+        .unwrap();
+        let staging = config.environment("staging").unwrap();
+        assert_eq!(staging.db_server, "staging.db");
+        assert_eq!(staging.db_name, "moonpump_staging");
+        assert_eq!(staging.db_user, "ops");
+        assert_eq!(staging.db_password, "secret");
+        assert!(!staging.production);
+    }
+
+    #[test]
+    fn unknown_environment_fields_are_refused() {
+        let error = load(
+            r#"
+            [environments.production]
+            db_sever = "db.server.com"
+            "#,
+        )
+        .unwrap_err();
+        // named by the cause, printed under the error
+        let mut causes = vec![error.to_string()];
+        let mut source = std::error::Error::source(&error);
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        assert!(
+            causes.iter().any(|cause| cause.contains("db_sever")),
+            "{:?}",
+            causes
+        );
+    }
+
+    fn with_staging() -> Config {
+        let mut config = Config::default();
+        config.environments.insert(
+            "staging".to_string(),
+            NamedEnvironment {
+                db_server: Some("staging.db".to_string()),
+                ..NamedEnvironment::default()
+            },
+        );
+        config
+    }
+
+    #[test]
+    fn start_environment_prefers_the_flag_then_default_env() {
+        let mut config = with_staging();
+        assert_eq!(config.start_environment(None), DEFAULT_ENVIRONMENT);
+        config.default_env = Some("staging".to_string());
+        assert_eq!(config.start_environment(None), "staging");
+        assert_eq!(config.start_environment(Some("default")), "default");
+    }
+
+    #[test]
+    fn default_is_the_top_level_and_unknown_names_list_the_known() {
+        let config = with_staging();
+        with_env(&[("XTOOL_DB_SERVER", None)], || {
+            assert_eq!(
+                config.environment(DEFAULT_ENVIRONMENT).unwrap().db_server,
+                config.db.db_server
+            );
+            assert_eq!(
+                config.environment("staging").unwrap().db_server,
+                "staging.db"
+            );
+        });
+        match config.environment("prod") {
+            Err(AppError::UnknownEnvironment { name, known }) => {
+                assert_eq!(name, "prod");
+                assert_eq!(known, ["default", "staging"]);
+            }
+            other => panic!("expected an unknown environment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn overrides_only_apply_to_default() {
+        let config = with_staging();
+        let vars = [
+            ("XTOOL_DB_SERVER", Some("override.db")),
+            ("XTOOL_DB_NAME", Some("override")),
+        ];
+        with_env(&vars, || {
+            let default = config.environment(DEFAULT_ENVIRONMENT).unwrap();
+            assert_eq!(default.db_server, "override.db");
+            assert_eq!(default.db_name, "override");
+            // neither set nor inherited by a named environment
+            let staging = config.environment("staging").unwrap();
+            assert_eq!(staging.db_server, "staging.db");
+            assert_eq!(staging.db_name, config.db.db_name);
+        });
+    }
+}
@@ -0,0 +1,104 @@
+use chrono::NaiveDateTime;
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Wrap},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::error::ConflictColumn;
+use crate::db::schema::TaskTable;
+use crate::popup_area;
+
+/// An edit refused because its row changed after the edit started.
+pub struct Conflict {
+    pub table: TaskTable,
+    pub key_value: String,
+    /// `update_time` of the row now, so overwriting expects it instead.
+    pub version: Option<NaiveDateTime>,
+    pub columns: Vec<ConflictColumn>,
+}
+
+impl Conflict {
+    /// The values the edit wanted to write.
+    pub fn values(&self) -> Vec<(&'static str, &str)> {
+        self.columns
+            .iter()
+            .map(|column| (column.column, column.ours.as_str()))
+            .collect()
+    }
+
+    /// The values the row has now, which overwriting expects to find.
+    pub fn originals(&self) -> Vec<(&'static str, &str)> {
+        self.columns
+            .iter()
+            .map(|column| (column.column, column.server.as_str()))
+            .collect()
+    }
+
+    /// Draw the server values next to ours, differing ones highlighted.
+    pub fn render(&self, frame: &mut Frame) {
+        let area = popup_area(frame.area(), 60, 50);
+        frame.render_widget(Clear, area);
+
+        let label_width = self
+            .columns
+            .iter()
+            .map(|column| column.column.width())
+            .max()
+            .unwrap_or(0);
+        let server_width = self
+            .columns
+            .iter()
+            .map(|column| column.server.width())
+            .max()
+            .unwrap_or(0)
+            .max("服务器".width());
+
+        let mut lines = vec![
+            Line::from(format!("任务 #{} 在编辑期间已被他人修改", self.key_value)).bold(),
+            Line::default(),
+            Line::from(format!(
+                "  {:label_width$}   {}{}   {}",
+                "",
+                "服务器",
+                " ".repeat(server_width - "服务器".width()),
+                "我们的"
+            ))
+            .fg(Color::DarkGray),
+        ];
+        let schema = self.table.schema();
+        for column in &self.columns {
+            let same = schema
+                .column(column.column)
+                .map_or(column.server == column.ours, |def| {
+                    def.same_value(&column.server, &column.ours)
+                });
+            let (server, ours) = if same {
+                (Style::default(), Style::default())
+            } else {
+                (
+                    Style::default().fg(Color::Red),
+                    Style::default().fg(Color::Green),
+                )
+            };
+            lines.push(Line::from(vec![
+                Span::raw(format!("  {:>label_width$} : ", column.column)),
+                Span::styled(column.server.as_str(), server),
+                Span::raw(" ".repeat(server_width - column.server.width() + 3)),
+                Span::styled(column.ours.as_str(), ours),
+            ]));
+        }
+
+        let block = Block::bordered()
+            .title("修改冲突")
+            .title_bottom("(Enter) 用我们的值覆盖 | (Esc) 放弃修改");
+        frame.render_widget(
+            Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(block),
+            area,
+        );
+    }
+}
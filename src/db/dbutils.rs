@@ -1,52 +1,165 @@
+use chrono::NaiveDateTime;
 use log::info;
-use serde::Serialize;
-use sqlx::mysql::MySqlPool;
-use sqlx::FromRow;
-
-#[derive(Debug, FromRow, Serialize, Clone)]
-pub struct MmVolumeTask {
-    pub id: String,
-    pub launch_id: String,
-    pub token_add: String,
-    pub target_volume: String,
-    pub do_status: String,
-    pub use_wallet_type: String,
-    pub remark: String,
-    pub buy_rate: String,
-    pub buy_per_low: String,
-    pub buy_per_high: String,
-    pub sell_percent: String,
-    pub frequent_low: String,
-    pub frequent_high: String,
-    pub real_sol: String,
-    pub create_time: String,
-    pub update_time: String,
-    pub col1: String,
-    pub col2: String,
-    pub col3: String,
+use sqlx::mysql::{MySql, MySqlPool};
+use sqlx::{Row, Transaction};
+
+use super::error::{ConflictColumn, DbError};
+use super::filter::Filter;
+use super::model::{DexVolumeTask, MmVolumeTask};
+use super::schema::{ColumnDef, TableSchema, TaskTable};
+
+/// Column bumped by every write, telling whether a row changed since it was read.
+const VERSION_COLUMN: &str = "update_time";
+
+/// Columns a copied task does not inherit, so the copy starts stopped.
+const RESET_ON_COPY: &[&str] = &["do_status"];
+
+/// Columns of `mm_volume_task` as `MmVolumeTask` reads them.
+const MM_SELECT: &str = "
+    CAST(id AS UNSIGNED) as id,
+    CAST(launch_id AS UNSIGNED) as launch_id,
+    token_add,
+    CAST(target_volume AS DECIMAL(38, 18)) as target_volume,
+    CAST(do_status AS SIGNED) as do_status,
+    CAST(use_wallet_type AS SIGNED) as use_wallet_type,
+    remark,
+    CAST(buy_rate AS DECIMAL(38, 18)) as buy_rate,
+    CAST(buy_per_low AS DECIMAL(38, 18)) as buy_per_low,
+    CAST(buy_per_high AS DECIMAL(38, 18)) as buy_per_high,
+    CAST(sell_percent AS DECIMAL(38, 18)) as sell_percent,
+    CAST(frequent_low AS DECIMAL(38, 18)) as frequent_low,
+    CAST(frequent_high AS DECIMAL(38, 18)) as frequent_high,
+    CAST(real_sol AS DECIMAL(38, 18)) as real_sol,
+    create_time,
+    update_time";
+
+/// Columns of `dex_volume_task` as `DexVolumeTask` reads them.
+const DEX_SELECT: &str = "
+    CAST(id AS UNSIGNED) as id,
+    pool_id,
+    token_add,
+    CAST(mm_type AS SIGNED) as mm_type,
+    remark,
+    CAST(target_price AS DECIMAL(38, 18)) as target_price,
+    CAST(stop_price_per AS DECIMAL(38, 18)) as stop_price_per,
+    CAST(do_status AS SIGNED) as do_status,
+    CAST(buy_rate AS DECIMAL(38, 18)) as buy_rate,
+    CAST(buy_per_low AS DECIMAL(38, 18)) as buy_per_low,
+    CAST(buy_per_high AS DECIMAL(38, 18)) as buy_per_high,
+    CAST(sell_percent AS DECIMAL(38, 18)) as sell_percent,
+    CAST(frequent_low AS DECIMAL(38, 18)) as frequent_low,
+    CAST(frequent_high AS DECIMAL(38, 18)) as frequent_high,
+    CAST(bsdiff AS DECIMAL(38, 18)) as bsdiff,
+    create_time,
+    update_time";
+
+/// A window of `limit` rows starting at `offset`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub offset: u64,
+    pub limit: u64,
+}
+
+impl Page {
+    pub const fn first(limit: u64) -> Self {
+        Self { offset: 0, limit }
+    }
+
+    /// 1-based number of this page.
+    pub fn number(&self) -> u64 {
+        self.offset / self.limit + 1
+    }
+
+    /// How many pages `total` rows fill, at least one.
+    pub fn count(&self, total: u64) -> u64 {
+        total.div_ceil(self.limit).max(1)
+    }
+
+    pub fn next(&self) -> Self {
+        Self {
+            offset: self.offset + self.limit,
+            limit: self.limit,
+        }
+    }
+
+    pub fn previous(&self) -> Self {
+        Self {
+            offset: self.offset.saturating_sub(self.limit),
+            limit: self.limit,
+        }
+    }
+
+    /// The last page holding any of `total` rows.
+    pub fn last(&self, total: u64) -> Self {
+        Self {
+            offset: (self.count(total) - 1) * self.limit,
+            limit: self.limit,
+        }
+    }
+
+    /// The page of `limit` rows that holds the first row of this one.
+    pub fn resized(&self, limit: u64) -> Self {
+        Self::holding(self.offset, limit)
+    }
+
+    /// The page of `limit` rows that holds the row at `offset`.
+    pub fn holding(offset: u64, limit: u64) -> Self {
+        Self {
+            offset: offset / limit * limit,
+            limit,
+        }
+    }
+}
+
+/// Column to order a task query by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub column: &'static str,
+    pub descending: bool,
+}
+
+impl Sort {
+    pub const fn ascending(column: &'static str) -> Self {
+        Self {
+            column,
+            descending: false,
+        }
+    }
+
+    pub fn reversed(self) -> Self {
+        Self {
+            column: self.column,
+            descending: !self.descending,
+        }
+    }
+}
+
+/// What an edit read before changing a row, so changes made since are not overwritten.
+#[derive(Debug, Clone, Copy)]
+pub struct Expected<'a> {
+    /// `update_time` of the row when the edit started.
+    pub version: Option<NaiveDateTime>,
+    /// Values the written columns had then, empty for NULL.
+    pub originals: &'a [(&'a str, &'a str)],
+}
+
+/// One page of a task query, with the number of rows matched across all pages.
+#[derive(Debug, Clone)]
+pub struct TaskPage<T> {
+    pub page: Page,
+    pub items: Vec<T>,
+    pub total: u64,
+    /// Highest id the query matches, so rows inserted later can be told apart.
+    pub newest: Option<u64>,
+    /// Rows the query matches that were inserted after the id asked about.
+    pub inserted: Option<Inserted>,
 }
 
-#[derive(Debug, FromRow, Serialize, Clone)]
-pub struct DexVolumeTask {
-    pub id: String,
-    pub pool_id: String,
-    pub token_add: String,
-    pub mm_type: String,
-    pub remark: String,
-    pub target_price: String,
-    pub stop_price_per: String,
-    pub do_status: String,
-    pub buy_rate: String,
-    pub buy_per_low: String,
-    pub buy_per_high: String,
-    pub sell_percent: String,
-    pub frequent_low: String,
-    pub frequent_high: String,
-    pub bsdiff: String,
-    pub create_time: String,
-    pub update_time: String,
-    pub copy: String,
-    pub del: String,
+/// How many rows matching a query have an id above `since`, i.e. were inserted after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Inserted {
+    pub since: u64,
+    pub count: u64,
 }
 
 pub struct Database {
@@ -55,182 +168,599 @@ pub struct Database {
 
 impl Database {
     // init pool
-    pub async fn new(database_url: &str) -> Result<Self, sqlx::Error> {
+    pub async fn new(database_url: &str) -> Result<Self, DbError> {
         let pool = MySqlPool::connect(database_url).await?;
         Ok(Database { pool })
     }
 
-    pub async fn get_all_mm_volume_task(
+    /// One page of the rows `filter` matches; with `since`, also count the rows
+    /// inserted after that id.
+    pub async fn get_mm_volume_task_page(
         &self,
-        search_word: &str,
-    ) -> Result<Vec<MmVolumeTask>, sqlx::Error> {
-        let search_key_word = format!("%{}%", search_word);
-        let mm_volume_tasks: Vec<MmVolumeTask> = sqlx::query_as::<_, MmVolumeTask>(
-            "SELECT 
-            IFNULL(CAST(id AS CHAR), '') as id, 
-            IFNULL(CAST(launch_id AS CHAR), '') as launch_id, 
-            IFNULL(token_add, '') as token_add , 
-            IFNULL(CAST(target_volume AS CHAR), '') as target_volume , 
-            IFNULL(CAST(do_status AS CHAR), '') as do_status , 
-            IFNULL(CAST(use_wallet_type AS CHAR), '') as use_wallet_type , 
-            IFNULL(remark, '') as remark, 
-            IFNULL(CAST(buy_rate AS CHAR), '') as buy_rate ,
-            IFNULL(CAST(buy_per_low AS CHAR), '') as buy_per_low ,
-            IFNULL(CAST(buy_per_high AS CHAR), '') as buy_per_high ,
-            IFNULL(CAST(sell_percent AS CHAR), '') as sell_percent ,
-            IFNULL(CAST(frequent_low AS CHAR), '') as frequent_low ,
-            IFNULL(CAST(frequent_high AS CHAR), '') as frequent_high ,
-            IFNULL(CAST(real_sol AS CHAR), '') as real_sol,
-            IFNULL(DATE_FORMAT(create_time, '%Y-%m-%d %H:%i:%s'), '') as create_time,
-            IFNULL(DATE_FORMAT(update_time, '%Y-%m-%d %H:%i:%s'), '') as update_time,
-            '' as col1,
-            '' as col2,
-            '' as col3
-            FROM mm_volume_task WHERE token_add like ? ",
-        )
-        .bind(search_key_word)
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(mm_volume_tasks)
+        filter: &Filter,
+        sort: Option<Sort>,
+        page: Page,
+        since: Option<u64>,
+    ) -> Result<TaskPage<MmVolumeTask>, DbError> {
+        let order_by = order_by(TaskTable::Mm.schema(), sort)?;
+        let sql = format!(
+            "SELECT {} FROM mm_volume_task {} {} LIMIT ? OFFSET ?",
+            MM_SELECT,
+            filter.where_clause(),
+            order_by
+        );
+        let mut query = sqlx::query_as::<_, MmVolumeTask>(&sql);
+        for bind in filter.binds() {
+            query = query.bind(bind);
+        }
+        let mm_volume_tasks = query
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch_all(&self.pool)
+            .await?;
+        let (total, newest, inserted) = self.count_rows(TaskTable::Mm, filter, since).await?;
+        Ok(TaskPage {
+            page,
+            items: mm_volume_tasks,
+            total,
+            newest,
+            inserted,
+        })
     }
 
-    pub async fn get_all_dex_volume_task(
+    /// One page of the rows `filter` matches; with `since`, also count the rows
+    /// inserted after that id.
+    pub async fn get_dex_volume_task_page(
         &self,
-        search_word: &str,
-    ) -> Result<Vec<DexVolumeTask>, sqlx::Error> {
-        let search_key_word = format!("%{}%", search_word);
-        let dex_volume_task = sqlx::query_as::<_, DexVolumeTask>(
-            "SELECT 
-            IFNULL(CAST(id AS CHAR), '') as id, 
-            IFNULL(pool_id, '') as pool_id , 
-            IFNULL(token_add, '') as token_add , 
-            IFNULL(CAST(mm_type AS CHAR), '') as mm_type , 
-            IFNULL(remark, '') as remark,
-            IFNULL(CAST(target_price AS CHAR), '')  as target_price ,
-            IFNULL(CAST(stop_price_per AS CHAR), '')  as stop_price_per ,
-            IFNULL(CAST(do_status AS CHAR), '')  as do_status , 
-            IFNULL(CAST(buy_rate AS CHAR), '')  as buy_rate , 
-            IFNULL(CAST(buy_per_low AS CHAR), '')  as buy_per_low , 
-            IFNULL(CAST(buy_per_high AS CHAR), '')  as buy_per_high , 
-            IFNULL(CAST(sell_percent AS CHAR), '')  as sell_percent , 
-            IFNULL(CAST(frequent_low AS CHAR), '')  as frequent_low , 
-            IFNULL(CAST(frequent_high AS CHAR), '')  as frequent_high , 
-            IFNULL(CAST(bsdiff AS CHAR), '')  as bsdiff ,
-            IFNULL(DATE_FORMAT(create_time, '%Y-%m-%d %H:%i:%s'), '') as create_time,
-            IFNULL(DATE_FORMAT(update_time, '%Y-%m-%d %H:%i:%s'), '') as update_time,
-            'copy' as copy,
-            'del'  as del
-            FROM dex_volume_task WHERE token_add like ? ",
-        )
-        .bind(search_key_word)
-        .fetch_all(&self.pool)
-        .await?;
-        Ok(dex_volume_task)
+        filter: &Filter,
+        sort: Option<Sort>,
+        page: Page,
+        since: Option<u64>,
+    ) -> Result<TaskPage<DexVolumeTask>, DbError> {
+        let order_by = order_by(TaskTable::Dex.schema(), sort)?;
+        let sql = format!(
+            "SELECT {} FROM dex_volume_task {} {} LIMIT ? OFFSET ?",
+            DEX_SELECT,
+            filter.where_clause(),
+            order_by
+        );
+        let mut query = sqlx::query_as::<_, DexVolumeTask>(&sql);
+        for bind in filter.binds() {
+            query = query.bind(bind);
+        }
+        let dex_volume_task = query
+            .bind(page.limit)
+            .bind(page.offset)
+            .fetch_all(&self.pool)
+            .await?;
+        let (total, newest, inserted) = self.count_rows(TaskTable::Dex, filter, since).await?;
+        Ok(TaskPage {
+            page,
+            items: dex_volume_task,
+            total,
+            newest,
+            inserted,
+        })
     }
 
-    pub async fn insert_user(&self, name: &str, email: &str) -> Result<u64, sqlx::Error> {
-        let result = sqlx::query("INSERT INTO users (name, email) VALUES (?, ?)")
-            .bind(name)
-            .bind(email)
-            .execute(&self.pool)
+    /// The `mm_volume_task` row with `id`, if there is one.
+    pub async fn get_mm_volume_task_by_id(&self, id: u64) -> Result<Option<MmVolumeTask>, DbError> {
+        let sql = format!("SELECT {} FROM mm_volume_task WHERE id = ?", MM_SELECT);
+        let task = sqlx::query_as::<_, MmVolumeTask>(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
-        Ok(result.last_insert_id())
+        Ok(task)
     }
 
-    pub async fn update_user(&self, id: i32, user_name: &str) -> Result<bool, sqlx::Error> {
-        let rows_affected =
-            sqlx::query("UPDATE tb_users SET userName = 'aaaaaaaaaa' WHERE accountId = 2")
-                // .bind(user_name)
-                // .bind(id)
-                .execute(&self.pool)
-                .await?
-                .rows_affected();
-        Ok(rows_affected > 0)
+    /// The `dex_volume_task` row with `id`, if there is one.
+    pub async fn get_dex_volume_task_by_id(
+        &self,
+        id: u64,
+    ) -> Result<Option<DexVolumeTask>, DbError> {
+        let sql = format!("SELECT {} FROM dex_volume_task WHERE id = ?", DEX_SELECT);
+        let task = sqlx::query_as::<_, DexVolumeTask>(&sql)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(task)
+    }
+
+    /// Number of rows a paged query matches across all pages, the highest id among
+    /// them, and how many have an id above `since`; read together so they agree.
+    async fn count_rows(
+        &self,
+        table: TaskTable,
+        filter: &Filter,
+        since: Option<u64>,
+    ) -> Result<(u64, Option<u64>, Option<Inserted>), DbError> {
+        let schema = table.schema();
+        let _sql = format!(
+            "SELECT CAST(COUNT(*) AS UNSIGNED), CAST(MAX(`{0}`) AS UNSIGNED), \
+            CAST(COUNT(CASE WHEN `{0}` > ? THEN 1 END) AS UNSIGNED) FROM `{1}` {2}",
+            schema.key,
+            schema.table,
+            filter.where_clause()
+        );
+        let mut query = sqlx::query_as(&_sql).bind(since.unwrap_or(u64::MAX));
+        for bind in filter.binds() {
+            query = query.bind(bind);
+        }
+        let (total, newest, count): (u64, Option<u64>, u64) = query.fetch_one(&self.pool).await?;
+        let inserted = since.map(|since| Inserted { since, count });
+        Ok((total, newest, inserted))
+    }
+
+    /// How many rows come before the row with `id` under `filter` and `sort`, or
+    /// `None` when the filter leaves the row out.
+    pub async fn row_offset(
+        &self,
+        table: TaskTable,
+        filter: &Filter,
+        sort: Option<Sort>,
+        id: u64,
+    ) -> Result<Option<u64>, DbError> {
+        let schema = table.schema();
+
+        let _sql = format!(
+            "SELECT CAST(COUNT(*) AS UNSIGNED) FROM `{}` {}",
+            schema.table,
+            filter.where_clause_with(&format!("`{}` = ?", schema.key))
+        );
+        let mut query = sqlx::query_scalar(&_sql);
+        for bind in filter.binds() {
+            query = query.bind(bind);
+        }
+        let matched: u64 = query.bind(id).fetch_one(&self.pool).await?;
+        if matched == 0 {
+            return Ok(None);
+        }
+
+        let (sort_column, before) = rows_before(schema, sort)?;
+        let _sql = format!(
+            "SELECT CAST(COUNT(*) AS UNSIGNED) FROM `{0}`, \
+            (SELECT `{1}` AS sort_value, `{2}` AS key_value FROM `{0}` WHERE `{2}` = ?) AS target {3}",
+            schema.table,
+            sort_column,
+            schema.key,
+            filter.where_clause_with(&before)
+        );
+        let mut query = sqlx::query_scalar(&_sql).bind(id);
+        for bind in filter.binds() {
+            query = query.bind(bind);
+        }
+        let offset: u64 = query.fetch_one(&self.pool).await?;
+        Ok(Some(offset))
     }
 
     pub async fn update_record(
         &self,
+        table: TaskTable,
         key_name: &str,
         key_value: &str,
         column_name: &str,
         column_value: &str,
-    ) -> Result<bool, sqlx::Error> {
+        expected: Expected<'_>,
+    ) -> Result<bool, DbError> {
+        self.update_columns(
+            table,
+            key_name,
+            key_value,
+            &[(column_name, column_value)],
+            expected,
+        )
+        .await
+    }
+
+    /// Write several columns of one row in a single UPDATE.
+    ///
+    /// If the row no longer has the `update_time` or the column values in
+    /// `expected`, nothing is written and `DbError::Conflict` holds the values it
+    /// has now. Comparing the values catches writers that leave `update_time`
+    /// alone, and writes within the same second.
+    pub async fn update_columns(
+        &self,
+        table: TaskTable,
+        key_name: &str,
+        key_value: &str,
+        values: &[(&str, &str)],
+        expected: Expected<'_>,
+    ) -> Result<bool, DbError> {
+        let schema = table.schema();
+        let key = schema.key_column(key_name)?;
+
+        let mut names = vec![];
+        let mut assignments = vec![];
+        let mut binds = vec![];
+        for (name, value) in values {
+            let column = schema.validate(name, value)?;
+            // identifiers come from the static registry, never from the caller
+            names.push(column.name);
+            assignments.push(format!("`{}` = ?", column.name));
+            binds.push(bind_value(column, value));
+        }
+
+        if assignments.is_empty() {
+            return Ok(false);
+        }
+        assignments.push(format!("`{}` = CURRENT_TIMESTAMP", VERSION_COLUMN));
+
+        let mut tx = self.pool.begin().await?;
+
+        let current = lock_version(&mut tx, schema, key_value).await?;
+        let server = lock_row_values(&mut tx, schema, key_value, &names).await?;
+        let changed_since = server.iter().any(|(name, server)| {
+            expected
+                .originals
+                .iter()
+                .find(|(column, _)| column == name)
+                .is_some_and(|(_, original)| {
+                    schema
+                        .column(name)
+                        .is_some_and(|column| !column.same_value(original, server))
+                })
+        });
+        if current != expected.version || changed_since {
+            let columns = server
+                .into_iter()
+                .zip(values)
+                .map(|((column, server), (_, ours))| ConflictColumn {
+                    column,
+                    // shown like the table shows it, not as the CAST text
+                    server: match schema.column(column) {
+                        Some(def) => def.normalize(&server),
+                        None => server,
+                    },
+                    ours: ours.to_string(),
+                })
+                .collect();
+            return Err(DbError::Conflict {
+                table: schema.table,
+                key: key_value.to_string(),
+                version: current,
+                columns,
+            });
+        }
+
+        // check the row invariants against the locked row, not the UI's copy of it
+        let changed: Vec<&str> = values.iter().map(|(name, _)| *name).collect();
+        let rule_columns = schema.rule_columns(&changed);
+        if !rule_columns.is_empty() {
+            let current = lock_row_values(&mut tx, schema, key_value, &rule_columns).await?;
+            let row: Vec<(&str, &str)> = current
+                .iter()
+                .map(|(name, current_value)| {
+                    let value = values
+                        .iter()
+                        .find(|(column, _)| column == name)
+                        .map_or(current_value.as_str(), |(_, value)| *value);
+                    (*name, value)
+                })
+                .collect();
+            schema.check_row(&row, &changed)?;
+        }
+
         let _sql = &format!(
-            "UPDATE mm_volume_task SET {} = ? WHERE {} = ? ",
-            column_name, key_name
+            "UPDATE `{}` SET {} WHERE `{}` = ? ",
+            schema.table,
+            assignments.join(", "),
+            key
         );
         info!("{}", _sql);
-        let rows_affected = sqlx::query(_sql)
-            // .bind(column_name)
-            .bind(column_value)
-            // .bind(key_name)
+        let mut query = sqlx::query(_sql);
+        for bind in binds {
+            query = query.bind(bind);
+        }
+        let rows_affected = query
             .bind(key_value)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?
             .rows_affected();
+        tx.commit().await?;
         Ok(rows_affected > 0)
     }
 
-    pub async fn copy_dex_record_by_id(&self, key_value: &str) -> Result<bool, sqlx::Error> {
-        let _sql = &format!(
-            "INSERT INTO dex_volume_task (
-                pool_id,
-                token_add,
-                mm_type,
-                remark,
-                target_price,
-                stop_price_per,
-                do_status,
-                buy_rate,
-                buy_per_low,
-                buy_per_high,
-                sell_percent,
-                frequent_low,
-                frequent_high,
-                bsdiff
-            ) 
-            SELECT 
-                pool_id,
-                token_add,
-                mm_type,
-                remark,
-                target_price,
-                stop_price_per,
-                NULL as do_status,
-                buy_rate,
-                buy_per_low,
-                buy_per_high,
-                sell_percent,
-                frequent_low,
-                frequent_high,
-                bsdiff
-            FROM dex_volume_task WHERE id = ? ",
-        );
-        // info!("{}", _sql);
-        let rows_affected = sqlx::query(_sql)
-            // .bind(column_name)
+    pub async fn insert_mm_volume_task(&self, values: &[(&str, &str)]) -> Result<u64, DbError> {
+        self.insert_record(TaskTable::Mm, values).await
+    }
+
+    pub async fn insert_dex_volume_task(&self, values: &[(&str, &str)]) -> Result<u64, DbError> {
+        self.insert_record(TaskTable::Dex, values).await
+    }
+
+    /// Insert a row from column/value pairs and return its id.
+    async fn insert_record(
+        &self,
+        table: TaskTable,
+        values: &[(&str, &str)],
+    ) -> Result<u64, DbError> {
+        let schema = table.schema();
+
+        let mut columns = vec![];
+        let mut binds = vec![];
+        for (name, value) in values {
+            let column = schema.validate(name, value)?;
+            columns.push(format!("`{}`", column.name));
+            binds.push(bind_value(column, value));
+        }
+        let changed: Vec<&str> = values.iter().map(|(name, _)| *name).collect();
+        schema.check_row(values, &changed)?;
+
+        let _sql = format!(
+            "INSERT INTO `{}` ({}) VALUES ({})",
+            schema.table,
+            columns.join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        info!("{}", _sql);
+        let mut query = sqlx::query(&_sql);
+        for bind in binds {
+            query = query.bind(bind);
+        }
+        let result = query.execute(&self.pool).await?;
+        Ok(result.last_insert_id())
+    }
+
+    /// Duplicate a row as a stopped task and return the new id.
+    pub async fn copy_record(&self, table: TaskTable, key_value: &str) -> Result<u64, DbError> {
+        let schema = table.schema();
+
+        let columns: Vec<&str> = schema
+            .editable_columns()
+            .map(|column| column.name)
+            .collect();
+        let select: Vec<String> = columns
+            .iter()
+            .map(|name| {
+                if RESET_ON_COPY.contains(name) {
+                    format!("NULL AS `{}`", name)
+                } else {
+                    format!("`{}`", name)
+                }
+            })
+            .collect();
+
+        let _sql = format!(
+            "INSERT INTO `{0}` ({1}) SELECT {2} FROM `{0}` WHERE `{3}` = ?",
+            schema.table,
+            columns
+                .iter()
+                .map(|name| format!("`{}`", name))
+                .collect::<Vec<_>>()
+                .join(", "),
+            select.join(", "),
+            schema.key
+        );
+        info!("{}", _sql);
+        let result = sqlx::query(&_sql)
             .bind(key_value)
             .execute(&self.pool)
-            .await?
-            .rows_affected();
-        Ok(rows_affected > 0)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(DbError::NotFound {
+                table: schema.table,
+                key: key_value.to_string(),
+            });
+        }
+        Ok(result.last_insert_id())
     }
 
-    pub async fn delete_dex_record_by_id(&self, key_value: &str) -> Result<bool, sqlx::Error> {
-        let rows_affected = sqlx::query("DELETE FROM dex_volume_task WHERE id = ?")
+    pub async fn delete_record(&self, table: TaskTable, key_value: &str) -> Result<bool, DbError> {
+        let schema = table.schema();
+        let _sql = format!("DELETE FROM `{}` WHERE `{}` = ?", schema.table, schema.key);
+        info!("{}", _sql);
+        let rows_affected = sqlx::query(&_sql)
             .bind(key_value)
             .execute(&self.pool)
             .await?
             .rows_affected();
         Ok(rows_affected > 0)
     }
+}
 
-    pub async fn delete_user(&self, id: i32) -> Result<bool, sqlx::Error> {
-        let rows_affected = sqlx::query("DELETE FROM users WHERE id = ?")
-            .bind(id)
-            .execute(&self.pool)
-            .await?
-            .rows_affected();
-        Ok(rows_affected > 0)
+/// ORDER BY clause for `sort`; the key breaks ties and is the order when unsorted.
+fn order_by(schema: &TableSchema, sort: Option<Sort>) -> Result<String, DbError> {
+    let Some(sort) = sort else {
+        return Ok(format!("ORDER BY `{}`", schema.key));
+    };
+
+    let column = schema
+        .column(sort.column)
+        .ok_or_else(|| DbError::UnknownColumn {
+            table: schema.table,
+            column: sort.column.to_string(),
+        })?;
+    let direction = if sort.descending { "DESC" } else { "ASC" };
+    Ok(format!(
+        "ORDER BY `{}` {}, `{}` {}",
+        column.name, direction, schema.key, direction
+    ))
+}
+
+/// The column `sort` orders by, and a condition keeping the rows `order_by` puts
+/// before the row of the derived table `target`.
+fn rows_before(
+    schema: &TableSchema,
+    sort: Option<Sort>,
+) -> Result<(&'static str, String), DbError> {
+    let Some(sort) = sort else {
+        return Ok((schema.key, format!("`{}` < target.key_value", schema.key)));
+    };
+
+    let column = schema
+        .column(sort.column)
+        .ok_or_else(|| DbError::UnknownColumn {
+            table: schema.table,
+            column: sort.column.to_string(),
+        })?;
+    // MySQL puts NULLs first in ascending order and last in descending order,
+    // which is how `col IS NOT NULL` compares
+    let before = if sort.descending { ">" } else { "<" };
+    Ok((
+        column.name,
+        format!(
+            "((`{0}` IS NOT NULL) {1} (target.sort_value IS NOT NULL) \
+            OR ((`{0}` IS NOT NULL) = (target.sort_value IS NOT NULL) \
+            AND (`{0}` {1} target.sort_value \
+            OR (`{0}` <=> target.sort_value AND `{2}` {1} target.key_value))))",
+            column.name, before, schema.key
+        ),
+    ))
+}
+
+/// The value to bind for `column`: an empty input on a nullable column is NULL.
+fn bind_value<'a>(column: &ColumnDef, value: &'a str) -> Option<&'a str> {
+    if column.nullable && value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Read the `update_time` of one row, locking the row until `tx` ends.
+async fn lock_version(
+    tx: &mut Transaction<'_, MySql>,
+    schema: &TableSchema,
+    key_value: &str,
+) -> Result<Option<NaiveDateTime>, DbError> {
+    let _sql = format!(
+        "SELECT `{}` FROM `{}` WHERE `{}` = ? FOR UPDATE",
+        VERSION_COLUMN, schema.table, schema.key
+    );
+
+    let row = sqlx::query(&_sql)
+        .bind(key_value)
+        .fetch_optional(&mut **tx)
+        .await?
+        .ok_or_else(|| DbError::NotFound {
+            table: schema.table,
+            key: key_value.to_string(),
+        })?;
+    Ok(row.try_get(VERSION_COLUMN)?)
+}
+
+/// Read `columns` of one row as text, locking the row until `tx` ends.
+async fn lock_row_values(
+    tx: &mut Transaction<'_, MySql>,
+    schema: &TableSchema,
+    key_value: &str,
+    columns: &[&'static str],
+) -> Result<Vec<(&'static str, String)>, DbError> {
+    let select = columns
+        .iter()
+        .map(|column| format!("CAST(`{0}` AS CHAR) AS `{0}`", column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _sql = format!(
+        "SELECT {} FROM `{}` WHERE `{}` = ? FOR UPDATE",
+        select, schema.table, schema.key
+    );
+
+    let Some(row) = sqlx::query(&_sql)
+        .bind(key_value)
+        .fetch_optional(&mut **tx)
+        .await?
+    else {
+        return Ok(vec![]);
+    };
+
+    let mut values = vec![];
+    for column in columns {
+        let value: Option<String> = row.try_get(*column)?;
+        values.push((*column, value.unwrap_or_default()));
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_numbers_and_counts() {
+        let page = Page {
+            offset: 40,
+            limit: 20,
+        };
+        assert_eq!(page.number(), 3);
+        assert_eq!(page.count(0), 1);
+        assert_eq!(page.count(40), 2);
+        assert_eq!(page.count(41), 3);
+    }
+
+    #[test]
+    fn page_moves() {
+        let page = Page::first(20);
+        assert_eq!(
+            page.next(),
+            Page {
+                offset: 20,
+                limit: 20
+            }
+        );
+        assert_eq!(page.previous(), page);
+        assert_eq!(
+            page.next().next().previous(),
+            Page {
+                offset: 20,
+                limit: 20
+            }
+        );
+    }
+
+    #[test]
+    fn last_page_holds_the_last_row() {
+        let page = Page::first(20);
+        assert_eq!(page.last(0), Page::first(20));
+        assert_eq!(
+            page.last(40),
+            Page {
+                offset: 20,
+                limit: 20
+            }
+        );
+        assert_eq!(
+            page.last(41),
+            Page {
+                offset: 40,
+                limit: 20
+            }
+        );
+    }
+
+    #[test]
+    fn resizing_keeps_the_first_row_in_view() {
+        let page = Page {
+            offset: 40,
+            limit: 20,
+        };
+        assert_eq!(
+            page.resized(15),
+            Page {
+                offset: 30,
+                limit: 15
+            }
+        );
+        assert_eq!(
+            page.resized(40),
+            Page {
+                offset: 40,
+                limit: 40
+            }
+        );
+        assert_eq!(
+            Page::holding(59, 20),
+            Page {
+                offset: 40,
+                limit: 20
+            }
+        );
+        assert_eq!(
+            Page::holding(60, 20),
+            Page {
+                offset: 60,
+                limit: 20
+            }
+        );
     }
 }
@@ -0,0 +1,145 @@
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DbError {
+    #[error("unknown column `{column}` in {table}")]
+    UnknownColumn { table: &'static str, column: String },
+
+    #[error("column `{column}` in {table} is read-only")]
+    ReadOnlyColumn { table: &'static str, column: String },
+
+    #[error("`{key}` is not the key column of {table}")]
+    InvalidKey { table: &'static str, key: String },
+
+    #[error("no row with id {key} in {table}")]
+    NotFound { table: &'static str, key: String },
+
+    #[error("row {key} in {table} was changed by someone else since the edit started")]
+    Conflict {
+        table: &'static str,
+        key: String,
+        /// The `update_time` the row has now, to overwrite it with.
+        version: Option<NaiveDateTime>,
+        columns: Vec<ConflictColumn>,
+    },
+
+    #[error(transparent)]
+    Invalid(#[from] ValidationError),
+
+    #[error(transparent)]
+    Sqlx(#[from] sqlx::Error),
+}
+
+/// A column an edit tried to write to a row that changed meanwhile.
+#[derive(Debug, Clone)]
+pub struct ConflictColumn {
+    pub column: &'static str,
+    /// Value in the database now, empty for NULL.
+    pub server: String,
+    /// Value the edit wanted to write.
+    pub ours: String,
+}
+
+/// Why a value was refused by a column spec.
+#[derive(Debug, Error, PartialEq)]
+pub enum ValidationError {
+    #[error("{column} is read-only")]
+    ReadOnly { column: &'static str },
+
+    #[error("{column} is required")]
+    Required { column: &'static str },
+
+    #[error("{column} must be a whole number")]
+    NotAnInteger { column: &'static str },
+
+    #[error("{column} must be a number")]
+    NotANumber { column: &'static str },
+
+    #[error("{column} allows at most {scale} decimal places")]
+    TooPrecise { column: &'static str, scale: u32 },
+
+    #[error("{column} must be at least {min}")]
+    BelowMin { column: &'static str, min: Decimal },
+
+    #[error("{column} must be at most {max}")]
+    AboveMax { column: &'static str, max: Decimal },
+
+    #[error("{column} must not be greater than {sibling} ({value})")]
+    AboveSibling {
+        column: &'static str,
+        sibling: &'static str,
+        value: Decimal,
+    },
+
+    #[error("{column} must not be less than {sibling} ({value})")]
+    BelowSibling {
+        column: &'static str,
+        sibling: &'static str,
+        value: Decimal,
+    },
+
+    #[error("{column} must be one of {allowed:?}")]
+    NotAllowed {
+        column: &'static str,
+        allowed: &'static [i64],
+    },
+
+    #[error("{column} is longer than {max_len} characters")]
+    TooLong {
+        column: &'static str,
+        max_len: usize,
+    },
+
+    #[error("{column} is not a valid Solana address")]
+    InvalidAddress { column: &'static str },
+}
+
+impl ValidationError {
+    /// The column whose value was refused.
+    pub fn column(&self) -> &'static str {
+        match self {
+            ValidationError::ReadOnly { column }
+            | ValidationError::Required { column }
+            | ValidationError::NotAnInteger { column }
+            | ValidationError::NotANumber { column }
+            | ValidationError::TooPrecise { column, .. }
+            | ValidationError::BelowMin { column, .. }
+            | ValidationError::AboveMax { column, .. }
+            | ValidationError::AboveSibling { column, .. }
+            | ValidationError::BelowSibling { column, .. }
+            | ValidationError::NotAllowed { column, .. }
+            | ValidationError::TooLong { column, .. }
+            | ValidationError::InvalidAddress { column } => column,
+        }
+    }
+}
+
+/// Why the text in the search box is not a valid filter.
+#[derive(Debug, Error, PartialEq)]
+pub enum FilterError {
+    #[error("unknown column `{column}`")]
+    UnknownColumn { column: String },
+
+    #[error("missing column before the operator in `{term}`")]
+    MissingColumn { term: String },
+
+    #[error("missing value in `{term}`")]
+    MissingValue { term: String },
+
+    #[error("unknown operator in `{term}`, expected one of = != < <= > >= ~")]
+    InvalidOperator { term: String },
+
+    #[error("only = and != compare with null in `{term}`")]
+    NullComparison { term: String },
+
+    #[error("{column} must be compared with a number")]
+    NotANumber { column: &'static str },
+
+    #[error("unterminated quote in `{term}`")]
+    UnterminatedQuote { term: String },
+
+    #[error("unexpected text after the closing quote in `{term}`")]
+    TrailingText { term: String },
+}
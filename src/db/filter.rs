@@ -0,0 +1,418 @@
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use super::error::FilterError;
+use super::schema::TableSchema;
+
+/// Column a bare word is matched against, as the plain search box did.
+const SEARCH_COLUMN: &str = "token_add";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `~`, the column contains the value.
+    Contains,
+}
+
+/// Operator symbols, two-char ones first so `>=` is not read as `>`.
+const OPERATORS: [(&str, Op); 7] = [
+    ("!=", Op::Ne),
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    ("=", Op::Eq),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+    ("~", Op::Contains),
+];
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "<>",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Contains => "LIKE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    /// `null` on a text column, also matching the empty strings older tools wrote.
+    Blank,
+    Text(String),
+    Number(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condition {
+    column: &'static str,
+    op: Op,
+    value: Value,
+}
+
+impl Condition {
+    fn sql(&self) -> String {
+        // identifiers come from the static registry, never from the input
+        match (&self.value, self.op) {
+            (Value::Null, Op::Eq) => format!("`{}` IS NULL", self.column),
+            (Value::Null, _) => format!("`{}` IS NOT NULL", self.column),
+            (Value::Blank, Op::Eq) => format!("(`{0}` IS NULL OR `{0}` = '')", self.column),
+            (Value::Blank, _) => format!("(`{0}` IS NOT NULL AND `{0}` <> '')", self.column),
+            // only the bound text is converted, so an index on the column still applies
+            (Value::Number(_), op) => {
+                format!("`{}` {} CAST(? AS DECIMAL(38, 18))", self.column, op.sql())
+            }
+            (Value::Text(_), op) => format!("`{}` {} ?", self.column, op.sql()),
+        }
+    }
+}
+
+/// Conditions typed in the search box, all of which a row must match.
+///
+/// `do_status=1 remark~"whale" target_price>0.5` keeps running tasks whose
+/// remark contains `whale` and whose target price is above 0.5. Values with
+/// spaces are quoted, `null` matches a column that is NULL (or empty, for
+/// text), and a word without an operator searches the token address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+impl Filter {
+    /// Parse `input` against the columns of `schema`.
+    pub fn parse(schema: &TableSchema, input: &str) -> Result<Self, FilterError> {
+        let conditions = terms(input)?
+            .iter()
+            .map(|term| condition(schema, term))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { conditions })
+    }
+
+    /// WHERE clause with a placeholder per bind, empty when nothing is filtered.
+    pub fn where_clause(&self) -> String {
+        if self.conditions.is_empty() {
+            return String::new();
+        }
+
+        let conditions: Vec<String> = self.conditions.iter().map(Condition::sql).collect();
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+
+    /// `where_clause` with `condition` added, whose placeholders are bound after `binds`.
+    pub fn where_clause_with(&self, condition: &str) -> String {
+        let mut conditions: Vec<String> = self.conditions.iter().map(Condition::sql).collect();
+        conditions.push(condition.to_string());
+        format!("WHERE {}", conditions.join(" AND "))
+    }
+
+    /// Values for the placeholders of `where_clause`, in order.
+    pub fn binds(&self) -> Vec<&str> {
+        self.conditions
+            .iter()
+            .filter_map(|condition| match &condition.value {
+                Value::Null | Value::Blank => None,
+                Value::Text(value) | Value::Number(value) => Some(value.as_str()),
+            })
+            .collect()
+    }
+}
+
+/// Split `input` on whitespace outside double quotes.
+fn terms(input: &str) -> Result<Vec<String>, FilterError> {
+    let mut terms = vec![];
+    let mut term = String::new();
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for c in input.chars() {
+        if escaped {
+            escaped = false;
+        } else if quoted && c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c.is_whitespace() && !quoted {
+            if !term.is_empty() {
+                terms.push(std::mem::take(&mut term));
+            }
+            continue;
+        }
+        term.push(c);
+    }
+
+    if quoted {
+        return Err(FilterError::UnterminatedQuote { term });
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+fn condition(schema: &TableSchema, term: &str) -> Result<Condition, FilterError> {
+    let Some((at, symbol, op)) = operator(term)? else {
+        return Ok(Condition {
+            column: SEARCH_COLUMN,
+            op: Op::Contains,
+            value: Value::Text(like_pattern(&unquote(term)?)),
+        });
+    };
+
+    let name = &term[..at];
+    if name.is_empty() {
+        return Err(FilterError::MissingColumn {
+            term: term.to_string(),
+        });
+    }
+    let column = schema
+        .column(name)
+        .ok_or_else(|| FilterError::UnknownColumn {
+            column: name.to_string(),
+        })?;
+
+    let raw = &term[at + symbol.len()..];
+    if raw.is_empty() {
+        return Err(FilterError::MissingValue {
+            term: term.to_string(),
+        });
+    }
+
+    let value = if raw == "null" {
+        if !matches!(op, Op::Eq | Op::Ne) {
+            return Err(FilterError::NullComparison {
+                term: term.to_string(),
+            });
+        }
+        if column.is_text() {
+            Value::Blank
+        } else {
+            Value::Null
+        }
+    } else {
+        let text = unquote(raw)?;
+        if op == Op::Contains {
+            Value::Text(like_pattern(&text))
+        } else if column.is_numeric() {
+            let number = Decimal::from_str(&text).map_err(|_| FilterError::NotANumber {
+                column: column.name,
+            })?;
+            Value::Number(number.to_string())
+        } else {
+            Value::Text(text)
+        }
+    };
+
+    Ok(Condition {
+        column: column.name,
+        op,
+        value,
+    })
+}
+
+/// Where the operator of `term` starts, if it has one before any quote.
+fn operator(term: &str) -> Result<Option<(usize, &'static str, Op)>, FilterError> {
+    for (at, c) in term.char_indices() {
+        match c {
+            '"' => return Ok(None),
+            '=' | '!' | '<' | '>' | '~' => {
+                return OPERATORS
+                    .iter()
+                    .find(|(symbol, _)| term[at..].starts_with(symbol))
+                    .map(|(symbol, op)| Some((at, *symbol, *op)))
+                    .ok_or_else(|| FilterError::InvalidOperator {
+                        term: term.to_string(),
+                    });
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// The text of a value, with surrounding quotes and backslash escapes removed.
+fn unquote(raw: &str) -> Result<String, FilterError> {
+    let Some(inner) = raw.strip_prefix('"') else {
+        return Ok(raw.to_string());
+    };
+
+    let mut text = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.extend(chars.next()),
+            '"' => {
+                if chars.next().is_some() {
+                    return Err(FilterError::TrailingText {
+                        term: raw.to_string(),
+                    });
+                }
+                return Ok(text);
+            }
+            c => text.push(c),
+        }
+    }
+
+    Err(FilterError::UnterminatedQuote {
+        term: raw.to_string(),
+    })
+}
+
+/// LIKE pattern matching `text` anywhere, with its wildcards taken literally.
+fn like_pattern(text: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::schema::TaskTable;
+
+    fn parse(input: &str) -> Result<Filter, FilterError> {
+        Filter::parse(TaskTable::Mm.schema(), input)
+    }
+
+    #[test]
+    fn empty_input_filters_nothing() {
+        let filter = parse("  ").unwrap();
+        assert_eq!(filter.where_clause(), "");
+        assert!(filter.binds().is_empty());
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_escapes() {
+        let filter = parse(r#"remark="a \"big\" whale" do_status=1"#).unwrap();
+        assert_eq!(
+            filter.where_clause(),
+            "WHERE `remark` = ? AND `do_status` = CAST(? AS DECIMAL(38, 18))"
+        );
+        assert_eq!(filter.binds(), vec![r#"a "big" whale"#, "1"]);
+    }
+
+    #[test]
+    fn unbalanced_quotes_are_refused() {
+        assert!(matches!(
+            parse(r#"remark="whale"#),
+            Err(FilterError::UnterminatedQuote { .. })
+        ));
+        assert!(matches!(
+            parse(r#"remark="whale"s"#),
+            Err(FilterError::TrailingText { .. })
+        ));
+    }
+
+    #[test]
+    fn two_char_operators_are_not_split() {
+        let filter = parse("buy_rate>=0.5").unwrap();
+        assert_eq!(
+            filter.where_clause(),
+            "WHERE `buy_rate` >= CAST(? AS DECIMAL(38, 18))"
+        );
+        let filter = parse("buy_rate>0.5 sell_percent<=10 launch_id!=3").unwrap();
+        assert_eq!(
+            filter.where_clause(),
+            "WHERE `buy_rate` > CAST(? AS DECIMAL(38, 18)) \
+            AND `sell_percent` <= CAST(? AS DECIMAL(38, 18)) \
+            AND `launch_id` <> CAST(? AS DECIMAL(38, 18))"
+        );
+        assert_eq!(filter.binds(), vec!["0.5", "10", "3"]);
+    }
+
+    #[test]
+    fn null_only_compares_for_equality() {
+        let filter = parse("do_status=null launch_id!=null").unwrap();
+        assert_eq!(
+            filter.where_clause(),
+            "WHERE `do_status` IS NULL AND `launch_id` IS NOT NULL"
+        );
+        assert!(filter.binds().is_empty());
+        assert_eq!(
+            parse("do_status<null"),
+            Err(FilterError::NullComparison {
+                term: "do_status<null".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn null_text_also_matches_empty_text() {
+        let filter = parse("remark=null token_add!=null").unwrap();
+        assert_eq!(
+            filter.where_clause(),
+            "WHERE (`remark` IS NULL OR `remark` = '') \
+            AND (`token_add` IS NOT NULL AND `token_add` <> '')"
+        );
+        assert!(filter.binds().is_empty());
+    }
+
+    #[test]
+    fn like_wildcards_are_taken_literally() {
+        let filter = parse(r"remark~50%_off\").unwrap();
+        assert_eq!(filter.where_clause(), "WHERE `remark` LIKE ?");
+        assert_eq!(filter.binds(), vec![r"%50\%\_off\\%"]);
+    }
+
+    #[test]
+    fn bare_words_search_the_token_address() {
+        let filter = parse("So1").unwrap();
+        assert_eq!(filter.where_clause(), "WHERE `token_add` LIKE ?");
+        assert_eq!(filter.binds(), vec!["%So1%"]);
+    }
+
+    #[test]
+    fn malformed_terms_are_refused() {
+        assert_eq!(
+            parse("wallet=1"),
+            Err(FilterError::UnknownColumn {
+                column: "wallet".to_string()
+            })
+        );
+        assert_eq!(
+            parse("buy_rate=high"),
+            Err(FilterError::NotANumber { column: "buy_rate" })
+        );
+        assert!(matches!(
+            parse("=1"),
+            Err(FilterError::MissingColumn { .. })
+        ));
+        assert!(matches!(
+            parse("remark="),
+            Err(FilterError::MissingValue { .. })
+        ));
+        assert!(matches!(
+            parse("remark!whale"),
+            Err(FilterError::InvalidOperator { .. })
+        ));
+    }
+
+    #[test]
+    fn extra_conditions_come_after_the_filter() {
+        let filter = parse("do_status=1").unwrap();
+        assert_eq!(
+            filter.where_clause_with("`id` = ?"),
+            "WHERE `do_status` = CAST(? AS DECIMAL(38, 18)) AND `id` = ?"
+        );
+        assert_eq!(
+            Filter::default().where_clause_with("`id` = ?"),
+            "WHERE `id` = ?"
+        );
+    }
+}
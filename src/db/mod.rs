@@ -1,2 +1,5 @@
 pub mod dbutils;
-
+pub mod error;
+pub mod filter;
+pub mod model;
+pub mod schema;
@@ -0,0 +1,141 @@
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use serde::{Serialize, Serializer};
+use sqlx::{
+    error::BoxDynError,
+    mysql::{MySql, MySqlTypeInfo, MySqlValueRef},
+    Decode, FromRow, Type,
+};
+
+/// An enum stored as an integer code.
+pub trait CodeEnum {
+    /// Every code with a named variant.
+    const CODES: &'static [i64];
+
+    fn code(&self) -> i64;
+
+    fn label(&self) -> &'static str;
+}
+
+/// Declares an enum stored as an integer code.
+///
+/// Codes this tool does not know about are kept as `Other` so rows written by
+/// newer services still load.
+macro_rules! code_enum {
+    ($(#[$meta:meta])* $name:ident { $($variant:ident = $code:literal => $label:literal,)+ }) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $name {
+            $($variant,)+
+            Other(i64),
+        }
+
+        impl CodeEnum for $name {
+            const CODES: &'static [i64] = &[$($code,)+];
+
+            fn code(&self) -> i64 {
+                match self {
+                    $($name::$variant => $code,)+
+                    $name::Other(code) => *code,
+                }
+            }
+
+            fn label(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $label,)+
+                    $name::Other(_) => "未知",
+                }
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(code: i64) -> Self {
+                match code {
+                    $($code => $name::$variant,)+
+                    other => $name::Other(other),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", self.code())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_i64(self.code())
+            }
+        }
+
+        impl Type<MySql> for $name {
+            fn type_info() -> MySqlTypeInfo {
+                <i64 as Type<MySql>>::type_info()
+            }
+
+            fn compatible(ty: &MySqlTypeInfo) -> bool {
+                <i64 as Type<MySql>>::compatible(ty)
+            }
+        }
+
+        impl<'r> Decode<'r, MySql> for $name {
+            fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+                Ok($name::from(<i64 as Decode<MySql>>::decode(value)?))
+            }
+        }
+    };
+}
+
+code_enum! {
+    /// `do_status` of both task tables.
+    DoStatus {
+        Stopped = 0 => "停止",
+        Running = 1 => "运行",
+    }
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct MmVolumeTask {
+    pub id: u64,
+    pub launch_id: Option<u64>,
+    pub token_add: Option<String>,
+    pub target_volume: Option<Decimal>,
+    pub do_status: Option<DoStatus>,
+    /// Raw code; its meanings are not confirmed yet.
+    pub use_wallet_type: Option<i64>,
+    pub remark: Option<String>,
+    pub buy_rate: Option<Decimal>,
+    pub buy_per_low: Option<Decimal>,
+    pub buy_per_high: Option<Decimal>,
+    pub sell_percent: Option<Decimal>,
+    pub frequent_low: Option<Decimal>,
+    pub frequent_high: Option<Decimal>,
+    pub real_sol: Option<Decimal>,
+    pub create_time: Option<NaiveDateTime>,
+    pub update_time: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Clone)]
+pub struct DexVolumeTask {
+    pub id: u64,
+    pub pool_id: Option<String>,
+    pub token_add: Option<String>,
+    /// Raw code; its meanings are not confirmed yet.
+    pub mm_type: Option<i64>,
+    pub remark: Option<String>,
+    pub target_price: Option<Decimal>,
+    pub stop_price_per: Option<Decimal>,
+    pub do_status: Option<DoStatus>,
+    pub buy_rate: Option<Decimal>,
+    pub buy_per_low: Option<Decimal>,
+    pub buy_per_high: Option<Decimal>,
+    pub sell_percent: Option<Decimal>,
+    pub frequent_low: Option<Decimal>,
+    pub frequent_high: Option<Decimal>,
+    pub bsdiff: Option<Decimal>,
+    pub create_time: Option<NaiveDateTime>,
+    pub update_time: Option<NaiveDateTime>,
+}
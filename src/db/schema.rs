@@ -0,0 +1,649 @@
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::error::{DbError, ValidationError};
+use super::model::{CodeEnum, DoStatus};
+
+lazy_static! {
+    /// Base58 encoded 32 byte public key.
+    static ref SOLANA_ADDRESS: Regex = Regex::new("^[1-9A-HJ-NP-Za-km-z]{32,44}$").unwrap();
+}
+
+/// The task tables that can be edited from the UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskTable {
+    Mm,
+    Dex,
+}
+
+impl TaskTable {
+    pub fn schema(&self) -> &'static TableSchema {
+        match self {
+            TaskTable::Mm => &MM_VOLUME_TASK,
+            TaskTable::Dex => &DEX_VOLUME_TASK,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnKind {
+    Integer,
+    /// A number with at most `scale` decimal places.
+    Decimal {
+        scale: u32,
+    },
+    /// One of the codes of a `CodeEnum`.
+    Code(&'static [i64]),
+    Text {
+        max_len: usize,
+    },
+    /// Base58 Solana account address.
+    Address,
+    Timestamp,
+}
+
+#[derive(Debug)]
+pub struct ColumnDef {
+    pub name: &'static str,
+    pub editable: bool,
+    /// An empty input is stored as NULL.
+    pub nullable: bool,
+    pub kind: ColumnKind,
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+}
+
+const fn int(value: u32) -> Decimal {
+    Decimal::from_parts(value, 0, 0, false, 0)
+}
+
+impl ColumnDef {
+    const fn new(name: &'static str, kind: ColumnKind) -> Self {
+        Self {
+            name,
+            editable: true,
+            nullable: false,
+            kind,
+            min: None,
+            max: None,
+        }
+    }
+
+    const fn key(name: &'static str) -> Self {
+        Self::new(name, ColumnKind::Integer).read_only()
+    }
+
+    const fn read_only(mut self) -> Self {
+        self.editable = false;
+        self
+    }
+
+    const fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    const fn min(mut self, min: Decimal) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    const fn max(mut self, max: Decimal) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Whether values compare as numbers rather than text.
+    pub fn is_numeric(&self) -> bool {
+        matches!(
+            self.kind,
+            ColumnKind::Integer | ColumnKind::Decimal { .. } | ColumnKind::Code(_)
+        )
+    }
+
+    /// Whether values are free text, where an empty string stands for no value.
+    pub fn is_text(&self) -> bool {
+        matches!(self.kind, ColumnKind::Text { .. } | ColumnKind::Address)
+    }
+
+    /// Whether `a` and `b` are the same value, e.g. `0.5` and `0.500000000` for a number.
+    pub fn same_value(&self, a: &str, b: &str) -> bool {
+        if self.is_numeric() {
+            if let (Ok(a), Ok(b)) = (Decimal::from_str(a), Decimal::from_str(b)) {
+                return a == b;
+            }
+        }
+        a == b
+    }
+
+    /// `value` as the table shows it, e.g. `0.5` for `0.500000000` read from a number column.
+    pub fn normalize(&self, value: &str) -> String {
+        match Decimal::from_str(value) {
+            Ok(number) if self.is_numeric() => number.normalize().to_string(),
+            _ => value.to_string(),
+        }
+    }
+
+    /// Initial value offered when creating a record.
+    pub fn default_value(&self) -> String {
+        if self.nullable {
+            return String::new();
+        }
+
+        match self.kind {
+            ColumnKind::Integer | ColumnKind::Decimal { .. } => {
+                self.min.unwrap_or(Decimal::ZERO).to_string()
+            }
+            ColumnKind::Code(codes) => codes.first().map(i64::to_string).unwrap_or_default(),
+            ColumnKind::Text { .. } | ColumnKind::Address | ColumnKind::Timestamp => String::new(),
+        }
+    }
+
+    /// Check a value typed by the user against this column's rules.
+    pub fn validate(&self, input: &str) -> Result<(), ValidationError> {
+        let column = self.name;
+
+        if !self.editable {
+            return Err(ValidationError::ReadOnly { column });
+        }
+
+        if input.is_empty() {
+            if self.nullable {
+                return Ok(());
+            }
+            return Err(ValidationError::Required { column });
+        }
+
+        match self.kind {
+            ColumnKind::Integer => {
+                let value = input
+                    .parse::<i64>()
+                    .map_err(|_| ValidationError::NotAnInteger { column })?;
+                self.check_range(Decimal::from(value))
+            }
+            ColumnKind::Decimal { scale } => {
+                let value =
+                    Decimal::from_str(input).map_err(|_| ValidationError::NotANumber { column })?;
+                if value.normalize().scale() > scale {
+                    return Err(ValidationError::TooPrecise { column, scale });
+                }
+                self.check_range(value)
+            }
+            ColumnKind::Code(allowed) => {
+                let code = input
+                    .parse::<i64>()
+                    .map_err(|_| ValidationError::NotAnInteger { column })?;
+                if !allowed.contains(&code) {
+                    return Err(ValidationError::NotAllowed { column, allowed });
+                }
+                Ok(())
+            }
+            ColumnKind::Text { max_len } => {
+                if input.chars().count() > max_len {
+                    return Err(ValidationError::TooLong { column, max_len });
+                }
+                Ok(())
+            }
+            ColumnKind::Address => {
+                if !SOLANA_ADDRESS.is_match(input) {
+                    return Err(ValidationError::InvalidAddress { column });
+                }
+                Ok(())
+            }
+            ColumnKind::Timestamp => Err(ValidationError::ReadOnly { column }),
+        }
+    }
+
+    fn check_range(&self, value: Decimal) -> Result<(), ValidationError> {
+        let column = self.name;
+
+        if let Some(min) = self.min {
+            if value < min {
+                return Err(ValidationError::BelowMin { column, min });
+            }
+        }
+
+        if let Some(max) = self.max {
+            if value > max {
+                return Err(ValidationError::AboveMax { column, max });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An invariant between the columns of one row.
+#[derive(Debug)]
+pub enum RowRule {
+    /// `low` must not be greater than `high`.
+    Ordered {
+        low: &'static str,
+        high: &'static str,
+    },
+    /// `column` must not be greater than `max`.
+    AtMost { column: &'static str, max: Decimal },
+}
+
+impl RowRule {
+    fn columns(&self) -> Vec<&'static str> {
+        match self {
+            RowRule::Ordered { low, high } => vec![low, high],
+            RowRule::AtMost { column, .. } => vec![column],
+        }
+    }
+
+    /// Report a violation from the side of the column that was `changed`.
+    fn check(
+        &self,
+        value_of: impl Fn(&str) -> Option<Decimal>,
+        changed: &[&str],
+    ) -> Result<(), ValidationError> {
+        match *self {
+            RowRule::Ordered { low, high } => match (value_of(low), value_of(high)) {
+                (Some(low_value), Some(high_value)) if low_value > high_value => {
+                    if changed.contains(&low) {
+                        Err(ValidationError::AboveSibling {
+                            column: low,
+                            sibling: high,
+                            value: high_value,
+                        })
+                    } else {
+                        Err(ValidationError::BelowSibling {
+                            column: high,
+                            sibling: low,
+                            value: low_value,
+                        })
+                    }
+                }
+                _ => Ok(()),
+            },
+            RowRule::AtMost { column, max } => match value_of(column) {
+                Some(value) if value > max => Err(ValidationError::AboveMax { column, max }),
+                _ => Ok(()),
+            },
+        }
+    }
+}
+
+/// Every column of a table, which of them can be written and which identifies a row.
+#[derive(Debug)]
+pub struct TableSchema {
+    pub table: &'static str,
+    pub key: &'static str,
+    pub columns: &'static [ColumnDef],
+    pub rules: &'static [RowRule],
+}
+
+impl TableSchema {
+    pub fn column(&self, name: &str) -> Option<&'static ColumnDef> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    pub fn editable_columns(&self) -> impl Iterator<Item = &'static ColumnDef> {
+        self.columns.iter().filter(|column| column.editable)
+    }
+
+    pub fn is_editable(&self, name: &str) -> bool {
+        self.column(name).is_some_and(|column| column.editable)
+    }
+
+    /// Look up a column that may be written, rejecting anything not in the registry.
+    pub fn editable_column(&self, name: &str) -> Result<&'static ColumnDef, DbError> {
+        let column = self.column(name).ok_or_else(|| DbError::UnknownColumn {
+            table: self.table,
+            column: name.to_string(),
+        })?;
+
+        if !column.editable {
+            return Err(DbError::ReadOnlyColumn {
+                table: self.table,
+                column: name.to_string(),
+            });
+        }
+
+        Ok(column)
+    }
+
+    /// Look up a writable column and check `input` against its rules.
+    pub fn validate(&self, name: &str, input: &str) -> Result<&'static ColumnDef, DbError> {
+        let column = self.editable_column(name)?;
+        column.validate(input)?;
+        Ok(column)
+    }
+
+    /// Columns read by the rules that involve any of `changed`.
+    pub fn rule_columns(&self, changed: &[&str]) -> Vec<&'static str> {
+        let mut columns: Vec<&'static str> = vec![];
+        for rule in self.touched_rules(changed) {
+            for column in rule.columns() {
+                if !columns.contains(&column) {
+                    columns.push(column);
+                }
+            }
+        }
+        columns
+    }
+
+    /// Check the row invariants that involve any of the `changed` columns.
+    ///
+    /// `row` holds the values the row would have after the change; rules on
+    /// untouched columns are skipped so an existing bad row can still be fixed.
+    pub fn check_row(&self, row: &[(&str, &str)], changed: &[&str]) -> Result<(), ValidationError> {
+        let value_of = |name: &str| {
+            row.iter()
+                .find(|(column, _)| *column == name)
+                .and_then(|(_, value)| Decimal::from_str(value).ok())
+        };
+
+        for rule in self.touched_rules(changed) {
+            rule.check(value_of, changed)?;
+        }
+
+        Ok(())
+    }
+
+    fn touched_rules<'a>(
+        &self,
+        changed: &'a [&str],
+    ) -> impl Iterator<Item = &'static RowRule> + 'a {
+        self.rules
+            .iter()
+            .filter(|rule| rule.columns().iter().any(|column| changed.contains(column)))
+    }
+
+    /// Check that `name` is the key column of the table.
+    pub fn key_column(&self, name: &str) -> Result<&'static str, DbError> {
+        if name != self.key {
+            return Err(DbError::InvalidKey {
+                table: self.table,
+                key: name.to_string(),
+            });
+        }
+
+        Ok(self.key)
+    }
+}
+
+const REMARK: ColumnKind = ColumnKind::Text { max_len: 255 };
+const SOL: ColumnKind = ColumnKind::Decimal { scale: 9 };
+const RATE: ColumnKind = ColumnKind::Decimal { scale: 4 };
+const PERCENT: ColumnKind = ColumnKind::Decimal { scale: 2 };
+const SECONDS: ColumnKind = ColumnKind::Decimal { scale: 3 };
+const PRICE: ColumnKind = ColumnKind::Decimal { scale: 18 };
+
+/// Trading parameters shared by both task tables.
+const TASK_RULES: &[RowRule] = &[
+    RowRule::Ordered {
+        low: "buy_per_low",
+        high: "buy_per_high",
+    },
+    RowRule::Ordered {
+        low: "frequent_low",
+        high: "frequent_high",
+    },
+    RowRule::AtMost {
+        column: "sell_percent",
+        max: int(100),
+    },
+];
+
+pub static MM_VOLUME_TASK: TableSchema = TableSchema {
+    table: "mm_volume_task",
+    key: "id",
+    columns: &[
+        ColumnDef::key("id"),
+        ColumnDef::new("launch_id", ColumnKind::Integer).min(Decimal::ZERO),
+        ColumnDef::new("token_add", ColumnKind::Address),
+        ColumnDef::new("target_volume", SOL).min(Decimal::ZERO),
+        ColumnDef::new("do_status", ColumnKind::Code(DoStatus::CODES)).nullable(),
+        // codes not confirmed yet, so any non-negative one is accepted
+        ColumnDef::new("use_wallet_type", ColumnKind::Integer).min(Decimal::ZERO),
+        ColumnDef::new("remark", REMARK).nullable(),
+        ColumnDef::new("buy_rate", RATE)
+            .min(Decimal::ZERO)
+            .max(Decimal::ONE),
+        ColumnDef::new("buy_per_low", SOL).min(Decimal::ZERO),
+        ColumnDef::new("buy_per_high", SOL).min(Decimal::ZERO),
+        ColumnDef::new("sell_percent", PERCENT).min(Decimal::ZERO),
+        ColumnDef::new("frequent_low", SECONDS).min(Decimal::ZERO),
+        ColumnDef::new("frequent_high", SECONDS).min(Decimal::ZERO),
+        ColumnDef::new("real_sol", SOL).min(Decimal::ZERO),
+        ColumnDef::new("create_time", ColumnKind::Timestamp).read_only(),
+        ColumnDef::new("update_time", ColumnKind::Timestamp).read_only(),
+    ],
+    rules: TASK_RULES,
+};
+
+pub static DEX_VOLUME_TASK: TableSchema = TableSchema {
+    table: "dex_volume_task",
+    key: "id",
+    columns: &[
+        ColumnDef::key("id"),
+        ColumnDef::new("pool_id", ColumnKind::Address),
+        ColumnDef::new("token_add", ColumnKind::Address),
+        // codes not confirmed yet, so any non-negative one is accepted
+        ColumnDef::new("mm_type", ColumnKind::Integer).min(Decimal::ZERO),
+        ColumnDef::new("remark", REMARK).nullable(),
+        ColumnDef::new("target_price", PRICE).min(Decimal::ZERO),
+        ColumnDef::new("stop_price_per", PERCENT)
+            .min(Decimal::ZERO)
+            .max(int(100)),
+        ColumnDef::new("do_status", ColumnKind::Code(DoStatus::CODES)).nullable(),
+        ColumnDef::new("buy_rate", RATE)
+            .min(Decimal::ZERO)
+            .max(Decimal::ONE),
+        ColumnDef::new("buy_per_low", SOL).min(Decimal::ZERO),
+        ColumnDef::new("buy_per_high", SOL).min(Decimal::ZERO),
+        ColumnDef::new("sell_percent", PERCENT).min(Decimal::ZERO),
+        ColumnDef::new("frequent_low", SECONDS).min(Decimal::ZERO),
+        ColumnDef::new("frequent_high", SECONDS).min(Decimal::ZERO),
+        ColumnDef::new("bsdiff", PRICE),
+        ColumnDef::new("create_time", ColumnKind::Timestamp).read_only(),
+        ColumnDef::new("update_time", ColumnKind::Timestamp).read_only(),
+    ],
+    rules: TASK_RULES,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(name: &str, input: &str) -> Result<(), ValidationError> {
+        MM_VOLUME_TASK.column(name).unwrap().validate(input)
+    }
+
+    #[test]
+    fn decimals_are_checked_for_range_and_scale() {
+        assert_eq!(validate("buy_rate", "0.5"), Ok(()));
+        assert_eq!(validate("buy_rate", "1"), Ok(()));
+        assert_eq!(
+            validate("buy_rate", "1.5"),
+            Err(ValidationError::AboveMax {
+                column: "buy_rate",
+                max: Decimal::ONE
+            })
+        );
+        assert_eq!(
+            validate("buy_rate", "0.12345"),
+            Err(ValidationError::TooPrecise {
+                column: "buy_rate",
+                scale: 4
+            })
+        );
+        // trailing zeros do not count as precision
+        assert_eq!(validate("buy_rate", "0.50000"), Ok(()));
+        assert_eq!(
+            validate("buy_rate", "half"),
+            Err(ValidationError::NotANumber { column: "buy_rate" })
+        );
+    }
+
+    #[test]
+    fn integers_and_codes() {
+        assert_eq!(
+            validate("launch_id", "-1"),
+            Err(ValidationError::BelowMin {
+                column: "launch_id",
+                min: Decimal::ZERO
+            })
+        );
+        assert_eq!(
+            validate("launch_id", "1.5"),
+            Err(ValidationError::NotAnInteger {
+                column: "launch_id"
+            })
+        );
+        assert_eq!(validate("do_status", "1"), Ok(()));
+        assert_eq!(
+            validate("do_status", "2"),
+            Err(ValidationError::NotAllowed {
+                column: "do_status",
+                allowed: DoStatus::CODES
+            })
+        );
+    }
+
+    #[test]
+    fn unconfirmed_codes_only_need_to_be_non_negative() {
+        assert_eq!(validate("use_wallet_type", "7"), Ok(()));
+        assert_eq!(
+            validate("use_wallet_type", "-1"),
+            Err(ValidationError::BelowMin {
+                column: "use_wallet_type",
+                min: Decimal::ZERO
+            })
+        );
+        assert_eq!(
+            DEX_VOLUME_TASK.column("mm_type").unwrap().validate("3"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn numbers_are_compared_and_shown_by_value() {
+        let column = MM_VOLUME_TASK.column("buy_rate").unwrap();
+        assert!(column.same_value("0.5", "0.500000000"));
+        assert!(!column.same_value("0.5", "0.05"));
+        assert_eq!(column.normalize("0.500000000"), "0.5");
+        assert_eq!(column.normalize(""), "");
+        let remark = MM_VOLUME_TASK.column("remark").unwrap();
+        assert!(!remark.same_value("0.5", "0.50"));
+        assert_eq!(remark.normalize("0.50"), "0.50");
+    }
+
+    #[test]
+    fn empty_values_need_a_nullable_column() {
+        assert_eq!(validate("remark", ""), Ok(()));
+        assert_eq!(
+            validate("target_volume", ""),
+            Err(ValidationError::Required {
+                column: "target_volume"
+            })
+        );
+    }
+
+    #[test]
+    fn text_and_addresses() {
+        assert_eq!(
+            validate("remark", &"x".repeat(256)),
+            Err(ValidationError::TooLong {
+                column: "remark",
+                max_len: 255
+            })
+        );
+        assert_eq!(
+            validate("token_add", "So11111111111111111111111111111111111111112"),
+            Ok(())
+        );
+        assert_eq!(
+            validate("token_add", "0x1234"),
+            Err(ValidationError::InvalidAddress {
+                column: "token_add"
+            })
+        );
+    }
+
+    #[test]
+    fn read_only_columns_are_refused() {
+        assert_eq!(
+            validate("id", "1"),
+            Err(ValidationError::ReadOnly { column: "id" })
+        );
+        assert_eq!(
+            validate("update_time", "2024-01-01 00:00:00"),
+            Err(ValidationError::ReadOnly {
+                column: "update_time"
+            })
+        );
+    }
+
+    #[test]
+    fn ordered_columns_report_from_the_changed_side() {
+        let row = [("buy_per_low", "2"), ("buy_per_high", "1")];
+        assert_eq!(
+            MM_VOLUME_TASK.check_row(&row, &["buy_per_low"]),
+            Err(ValidationError::AboveSibling {
+                column: "buy_per_low",
+                sibling: "buy_per_high",
+                value: Decimal::ONE
+            })
+        );
+        assert_eq!(
+            MM_VOLUME_TASK.check_row(&row, &["buy_per_high"]),
+            Err(ValidationError::BelowSibling {
+                column: "buy_per_high",
+                sibling: "buy_per_low",
+                value: Decimal::TWO
+            })
+        );
+        let equal = [("frequent_low", "5"), ("frequent_high", "5.000")];
+        assert_eq!(DEX_VOLUME_TASK.check_row(&equal, &["frequent_low"]), Ok(()));
+    }
+
+    #[test]
+    fn rules_on_untouched_columns_are_skipped() {
+        let row = [
+            ("buy_per_low", "2"),
+            ("buy_per_high", "1"),
+            ("sell_percent", "50"),
+        ];
+        assert_eq!(MM_VOLUME_TASK.check_row(&row, &["sell_percent"]), Ok(()));
+        // a missing or empty sibling leaves nothing to compare
+        let row = [("buy_per_low", "2"), ("buy_per_high", "")];
+        assert_eq!(MM_VOLUME_TASK.check_row(&row, &["buy_per_low"]), Ok(()));
+    }
+
+    #[test]
+    fn sell_percent_is_at_most_100() {
+        assert_eq!(
+            DEX_VOLUME_TASK.check_row(&[("sell_percent", "100")], &["sell_percent"]),
+            Ok(())
+        );
+        assert_eq!(
+            DEX_VOLUME_TASK.check_row(&[("sell_percent", "100.5")], &["sell_percent"]),
+            Err(ValidationError::AboveMax {
+                column: "sell_percent",
+                max: int(100)
+            })
+        );
+    }
+
+    #[test]
+    fn rule_columns_cover_the_rules_of_the_changed_columns() {
+        assert_eq!(
+            MM_VOLUME_TASK.rule_columns(&["buy_per_high", "remark"]),
+            vec!["buy_per_low", "buy_per_high"]
+        );
+        assert_eq!(
+            MM_VOLUME_TASK.rule_columns(&["sell_percent", "frequent_low"]),
+            vec!["frequent_low", "frequent_high", "sell_percent"]
+        );
+        assert!(MM_VOLUME_TASK.rule_columns(&["remark"]).is_empty());
+    }
+}
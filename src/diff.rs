@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::db::dbutils::TaskPage;
+use crate::display::TaskRow;
+
+/// How long a changed or added row stays highlighted.
+const HIGHLIGHT: Duration = Duration::from_secs(5);
+
+enum Change {
+    Added,
+    /// Indexes of the columns whose value changed.
+    Updated(Vec<usize>),
+}
+
+struct Mark {
+    change: Change,
+    at: Instant,
+}
+
+/// Rows a background refresh changed or added, by id, while they are highlighted.
+#[derive(Default)]
+pub struct Changes {
+    marks: HashMap<u64, Mark>,
+}
+
+/// Rows inserted into and deleted from a query between two loads.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub inserted: u64,
+    pub deleted: u64,
+}
+
+impl Changes {
+    /// Compare a fresh load of a page with the rows shown, which the query matched
+    /// `old_total` of with ids up to `old_newest`.
+    ///
+    /// Inserted rows get new ids, so a row new to the page is only marked added
+    /// when its id is above `old_newest`; others merely moved across the edge of
+    /// the page. The counts come from the rows inserted since `old_newest`, so an
+    /// insert and a delete in the same interval are both reported; when the load
+    /// did not count from `old_newest`, only the change in total is known.
+    pub fn record<T: TaskRow>(
+        &mut self,
+        old: &[T],
+        old_total: u64,
+        old_newest: Option<u64>,
+        new: &TaskPage<T>,
+    ) -> Tally {
+        let now = Instant::now();
+        self.marks.retain(|_, mark| mark.at.elapsed() < HIGHLIGHT);
+
+        let old_values: HashMap<u64, Vec<String>> =
+            old.iter().map(|data| (data.id(), data.values())).collect();
+        for data in &new.items {
+            let change = match old_values.get(&data.id()) {
+                None if old_newest.is_none_or(|newest| data.id() > newest) => Change::Added,
+                None => continue,
+                Some(old_values) => {
+                    let columns: Vec<usize> = data
+                        .values()
+                        .iter()
+                        .zip(old_values)
+                        .enumerate()
+                        .filter(|(_, (new, old))| new != old)
+                        .map(|(column, _)| column)
+                        .collect();
+                    if columns.is_empty() {
+                        continue;
+                    }
+                    Change::Updated(columns)
+                }
+            };
+            self.marks.insert(data.id(), Mark { change, at: now });
+        }
+
+        match new.inserted {
+            Some(inserted) if Some(inserted.since) == old_newest => Tally {
+                inserted: inserted.count,
+                deleted: (old_total + inserted.count).saturating_sub(new.total),
+            },
+            _ => Tally {
+                inserted: new.total.saturating_sub(old_total),
+                deleted: old_total.saturating_sub(new.total),
+            },
+        }
+    }
+
+    pub fn is_added(&self, id: u64) -> bool {
+        matches!(self.live(id), Some(Change::Added))
+    }
+
+    /// Columns of row `id` that changed in a recent refresh.
+    pub fn changed_columns(&self, id: u64) -> &[usize] {
+        match self.live(id) {
+            Some(Change::Updated(columns)) => columns,
+            _ => &[],
+        }
+    }
+
+    fn live(&self, id: u64) -> Option<&Change> {
+        self.marks
+            .get(&id)
+            .filter(|mark| mark.at.elapsed() < HIGHLIGHT)
+            .map(|mark| &mark.change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+    use crate::db::dbutils::{Inserted, Page};
+
+    struct Task(u64, &'static str);
+
+    impl TaskRow for Task {
+        const COLUMNS: &'static [&'static str] = &["id", "remark"];
+
+        fn id(&self) -> u64 {
+            self.0
+        }
+
+        fn version(&self) -> Option<NaiveDateTime> {
+            None
+        }
+
+        fn values(&self) -> Vec<String> {
+            vec![self.0.to_string(), self.1.to_string()]
+        }
+    }
+
+    fn page(items: Vec<Task>, total: u64, inserted: Option<Inserted>) -> TaskPage<Task> {
+        let newest = items.iter().map(TaskRow::id).max();
+        TaskPage {
+            page: Page::first(3),
+            items,
+            total,
+            newest,
+            inserted,
+        }
+    }
+
+    #[test]
+    fn changed_cells_are_marked() {
+        let mut changes = Changes::default();
+        let old = [Task(1, "a"), Task(2, "b")];
+        let new = page(vec![Task(1, "a"), Task(2, "c")], 2, None);
+        changes.record(&old, 2, Some(2), &new);
+        assert_eq!(changes.changed_columns(1), &[] as &[usize]);
+        assert_eq!(changes.changed_columns(2), &[1]);
+    }
+
+    #[test]
+    fn an_insert_and_a_delete_are_both_counted() {
+        let mut changes = Changes::default();
+        let old = [Task(1, ""), Task(2, ""), Task(3, "")];
+        // 2 deleted and 9 inserted between the loads, leaving the total at 5
+        let since = Inserted { since: 8, count: 1 };
+        let new = page(vec![Task(1, ""), Task(3, ""), Task(4, "")], 5, Some(since));
+        let tally = changes.record(&old, 5, Some(8), &new);
+        assert_eq!(
+            tally,
+            Tally {
+                inserted: 1,
+                deleted: 1
+            }
+        );
+        // 4 only moved up from the next page
+        assert!(!changes.is_added(4));
+    }
+
+    #[test]
+    fn rows_with_new_ids_are_marked_added() {
+        let mut changes = Changes::default();
+        let old = [Task(1, ""), Task(2, "")];
+        let since = Inserted { since: 2, count: 1 };
+        let new = page(vec![Task(1, ""), Task(2, ""), Task(3, "")], 3, Some(since));
+        let tally = changes.record(&old, 2, Some(2), &new);
+        assert!(changes.is_added(3));
+        assert_eq!(
+            tally,
+            Tally {
+                inserted: 1,
+                deleted: 0
+            }
+        );
+    }
+
+    #[test]
+    fn without_a_count_from_the_shown_rows_only_the_total_is_known() {
+        let mut changes = Changes::default();
+        let old = [Task(1, ""), Task(2, "")];
+        let since = Inserted { since: 1, count: 1 };
+        let new = page(vec![Task(1, "")], 1, Some(since));
+        assert_eq!(
+            changes.record(&old, 2, Some(2), &new),
+            Tally {
+                inserted: 0,
+                deleted: 1
+            }
+        );
+        assert_eq!(
+            changes.record(&old, 2, Some(2), &page(vec![], 1, None)),
+            Tally {
+                inserted: 0,
+                deleted: 1
+            }
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use std::fmt::Display;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::model::{CodeEnum, DexVolumeTask, MmVolumeTask};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A task record as shown in the table: one formatted cell per column.
+pub trait TaskRow {
+    /// Column names in table order, matching `values` and `cells`.
+    const COLUMNS: &'static [&'static str];
+
+    fn id(&self) -> u64;
+
+    /// The `update_time` an edit of this row expects to find unchanged.
+    fn version(&self) -> Option<NaiveDateTime>;
+
+    /// Raw column values as typed into the editor, empty for NULL.
+    fn values(&self) -> Vec<String>;
+
+    /// Column values formatted for the table.
+    fn cells(&self) -> Vec<String> {
+        self.values()
+    }
+}
+
+fn replace_cell(cells: &mut [String], columns: &[&str], column: &str, cell: String) {
+    if let Some(index) = columns.iter().position(|name| *name == column) {
+        cells[index] = cell;
+    }
+}
+
+fn text(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+fn decimal(value: &Option<Decimal>) -> String {
+    value
+        .map(|value| value.normalize().to_string())
+        .unwrap_or_default()
+}
+
+fn code<T: Display>(value: &Option<T>) -> String {
+    value.as_ref().map(ToString::to_string).unwrap_or_default()
+}
+
+fn labelled<T: CodeEnum>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map(|value| format!("{} {}", value.code(), value.label()))
+        .unwrap_or_default()
+}
+
+fn time(value: &Option<NaiveDateTime>) -> String {
+    value
+        .map(|value| value.format(TIME_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+impl TaskRow for MmVolumeTask {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "launch_id",
+        "token_add",
+        "target_volume",
+        "do_status",
+        "use_wallet_type",
+        "remark",
+        "buy_rate",
+        "buy_per_low",
+        "buy_per_high",
+        "sell_percent",
+        "frequent_low",
+        "frequent_high",
+        "real_sol",
+        "create_time",
+        "update_time",
+    ];
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn version(&self) -> Option<NaiveDateTime> {
+        self.update_time
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            code(&self.launch_id),
+            text(&self.token_add),
+            decimal(&self.target_volume),
+            code(&self.do_status),
+            code(&self.use_wallet_type),
+            text(&self.remark),
+            decimal(&self.buy_rate),
+            decimal(&self.buy_per_low),
+            decimal(&self.buy_per_high),
+            decimal(&self.sell_percent),
+            decimal(&self.frequent_low),
+            decimal(&self.frequent_high),
+            decimal(&self.real_sol),
+            time(&self.create_time),
+            time(&self.update_time),
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let mut cells = self.values();
+        replace_cell(
+            &mut cells,
+            Self::COLUMNS,
+            "do_status",
+            labelled(&self.do_status),
+        );
+        cells
+    }
+}
+
+impl TaskRow for DexVolumeTask {
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "pool_id",
+        "token_add",
+        "mm_type",
+        "remark",
+        "target_price",
+        "stop_price_per",
+        "do_status",
+        "buy_rate",
+        "buy_per_low",
+        "buy_per_high",
+        "sell_percent",
+        "frequent_low",
+        "frequent_high",
+        "bsdiff",
+        "create_time",
+        "update_time",
+    ];
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn version(&self) -> Option<NaiveDateTime> {
+        self.update_time
+    }
+
+    fn values(&self) -> Vec<String> {
+        vec![
+            self.id.to_string(),
+            text(&self.pool_id),
+            text(&self.token_add),
+            code(&self.mm_type),
+            text(&self.remark),
+            decimal(&self.target_price),
+            decimal(&self.stop_price_per),
+            code(&self.do_status),
+            decimal(&self.buy_rate),
+            decimal(&self.buy_per_low),
+            decimal(&self.buy_per_high),
+            decimal(&self.sell_percent),
+            decimal(&self.frequent_low),
+            decimal(&self.frequent_high),
+            decimal(&self.bsdiff),
+            time(&self.create_time),
+            time(&self.update_time),
+        ]
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let mut cells = self.values();
+        replace_cell(
+            &mut cells,
+            Self::COLUMNS,
+            "do_status",
+            labelled(&self.do_status),
+        );
+        cells
+    }
+}
+
+/// Longest display width of every column, in table order.
+pub fn column_widths<T: TaskRow>(items: &[T]) -> Vec<u16> {
+    let mut widths = vec![0; T::COLUMNS.len()];
+    for item in items {
+        for (width, cell) in widths.iter_mut().zip(item.cells()) {
+            #[allow(clippy::cast_possible_truncation)]
+            let cell_width = cell.width() as u16;
+            *width = (*width).max(cell_width);
+        }
+    }
+    widths
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::Block,
+    Frame,
+};
+
+use crate::db::dbutils::Database;
+use crate::render_dropdown;
+
+/// The database the session works on, shared with the refresh tasks.
+#[derive(Clone)]
+pub struct Connection {
+    /// Name of the environment, tagging what the refresh tasks send back.
+    pub env: String,
+    pub production: bool,
+    pub db: Arc<Database>,
+}
+
+/// How the name of an environment is drawn: red for production, so it is never missed.
+pub fn badge_style(production: bool) -> Style {
+    let style = if production {
+        Style::default().fg(Color::White).bg(Color::Red)
+    } else {
+        Style::default().fg(Color::Black).bg(Color::Cyan)
+    };
+    style.add_modifier(Modifier::BOLD)
+}
+
+/// Dropdown under the environment button, listing the configured databases.
+pub struct EnvMenu {
+    /// Names, with whether each is production.
+    entries: Vec<(String, bool)>,
+    selected: usize,
+}
+
+impl EnvMenu {
+    /// A menu over `entries` with `active` under the cursor.
+    pub fn new(entries: Vec<(String, bool)>, active: &str) -> Self {
+        let selected = entries
+            .iter()
+            .position(|(name, _)| name == active)
+            .unwrap_or(0);
+        Self { entries, selected }
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.entries.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+    }
+
+    /// Name of the environment under the cursor.
+    pub fn choose(&self) -> Option<&str> {
+        self.entries
+            .get(self.selected)
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Draw the dropdown hanging from `anchor`.
+    pub fn render(&self, frame: &mut Frame, anchor: Rect) {
+        let lines: Vec<Line> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, (name, production))| {
+                let mut style = badge_style(*production);
+                if i == self.selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Line::from(format!(" {} ", name)).style(style)
+            })
+            .collect();
+
+        let block = Block::bordered()
+            .title("数据库环境")
+            .title_bottom("(Enter) 切换 | (Esc) 取消");
+        render_dropdown(frame, anchor, lines, block);
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::db::error::{DbError, FilterError};
+
+/// Why the tool could not start or keep running.
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("no {file_name} found, searched: {}", list(.searched))]
+    MissingFile {
+        file_name: String,
+        searched: Vec<PathBuf>,
+    },
+
+    #[error("no environment named `{name}` in the config, known: {}", known.join(", "))]
+    UnknownEnvironment { name: String, known: Vec<String> },
+
+    #[error("environment variable {name} has an invalid value `{value}`")]
+    InvalidEnv { name: String, value: String },
+
+    #[error("failed to load configuration: {0}")]
+    Config(#[from] confy::ConfyError),
+
+    #[error("failed to read or write presets: {0}")]
+    Presets(confy::ConfyError),
+
+    #[error("failed to set up logging: {0}")]
+    Logging(String),
+
+    #[error("invalid argument: {0}")]
+    InvalidArgument(String),
+
+    #[error("invalid filter: {0}")]
+    Filter(#[from] FilterError),
+
+    #[error(transparent)]
+    Db(#[from] DbError),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn list(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,328 @@
+use chrono::NaiveDateTime;
+use ratatui::{
+    layout::Position,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::schema::{ColumnDef, TaskTable};
+use crate::popup_area;
+
+pub struct FormField {
+    pub column: &'static ColumnDef,
+    pub value: String,
+    /// Value loaded from the record being edited.
+    pub original: Option<String>,
+    pub error: Option<String>,
+}
+
+impl FormField {
+    fn is_changed(&self) -> bool {
+        self.original
+            .as_ref()
+            .is_some_and(|original| *original != self.value)
+    }
+}
+
+pub enum FormMode {
+    Insert,
+    Edit {
+        key_value: String,
+        /// `update_time` of the record when the form opened.
+        version: Option<NaiveDateTime>,
+    },
+}
+
+/// A popup listing the columns of a record, one per line.
+pub struct Form {
+    pub table: TaskTable,
+    pub mode: FormMode,
+    title: String,
+    pub fields: Vec<FormField>,
+    focused: usize,
+    /// Cursor position in the focused field, in chars.
+    cursor: usize,
+    /// Error not tied to a single field, e.g. a failed INSERT.
+    pub error: Option<String>,
+}
+
+impl Form {
+    /// A form for a new record, pre-filled with column defaults.
+    pub fn insert(table: TaskTable) -> Self {
+        let title = match table {
+            TaskTable::Mm => "新建 MM 任务",
+            TaskTable::Dex => "新建 DEX 任务",
+        };
+        let fields = table
+            .schema()
+            .editable_columns()
+            .map(|column| FormField {
+                column,
+                value: column.default_value(),
+                original: None,
+                error: None,
+            })
+            .collect();
+
+        Self::new(table, FormMode::Insert, title.to_string(), fields)
+    }
+
+    /// A form for an existing record, showing every column of `row`.
+    pub fn edit(
+        table: TaskTable,
+        key_value: String,
+        version: Option<NaiveDateTime>,
+        row: Vec<(&'static str, String)>,
+    ) -> Self {
+        let title = match table {
+            TaskTable::Mm => format!("编辑 MM 任务 #{}", key_value),
+            TaskTable::Dex => format!("编辑 DEX 任务 #{}", key_value),
+        };
+        let schema = table.schema();
+        let fields = row
+            .into_iter()
+            .filter_map(|(name, value)| {
+                Some(FormField {
+                    column: schema.column(name)?,
+                    value: value.clone(),
+                    original: Some(value),
+                    error: None,
+                })
+            })
+            .collect();
+
+        Self::new(table, FormMode::Edit { key_value, version }, title, fields)
+    }
+
+    fn new(table: TaskTable, mode: FormMode, title: String, fields: Vec<FormField>) -> Self {
+        let mut form = Self {
+            table,
+            mode,
+            title,
+            fields,
+            focused: 0,
+            cursor: 0,
+            error: None,
+        };
+        form.focused = form.editable_index(0, 1);
+        form.cursor = form.focused_len();
+        form
+    }
+
+    /// Column/value pairs to write: every field of a new record, only the changed ones otherwise.
+    pub fn values(&self) -> Vec<(&'static str, &str)> {
+        self.fields
+            .iter()
+            .filter(|field| match self.mode {
+                FormMode::Insert => field.column.editable,
+                FormMode::Edit { .. } => field.is_changed(),
+            })
+            .map(|field| (field.column.name, field.value.as_str()))
+            .collect()
+    }
+
+    /// Values the fields of `values` were loaded with, empty for a new record.
+    pub fn originals(&self) -> Vec<(&'static str, &str)> {
+        self.fields
+            .iter()
+            .filter(|field| field.is_changed())
+            .filter_map(|field| Some((field.column.name, field.original.as_deref()?)))
+            .collect()
+    }
+
+    /// Every field value, as the row would look once saved.
+    fn row(&self) -> Vec<(&'static str, &str)> {
+        self.fields
+            .iter()
+            .map(|field| (field.column.name, field.value.as_str()))
+            .collect()
+    }
+
+    /// Check the fields to write and the row invariants, returning true when the form can be saved.
+    pub fn validate(&mut self) -> bool {
+        let to_write: Vec<&str> = self.values().iter().map(|(name, _)| *name).collect();
+        for field in self.fields.iter_mut() {
+            field.error = None;
+            if to_write.contains(&field.column.name) {
+                field.error = field
+                    .column
+                    .validate(&field.value)
+                    .err()
+                    .map(|e| e.to_string());
+            }
+        }
+
+        if self.fields.iter().all(|field| field.error.is_none()) {
+            let values = self.row();
+            let changed = to_write;
+            if let Err(e) = self.table.schema().check_row(&values, &changed) {
+                if let Some(field) = self
+                    .fields
+                    .iter_mut()
+                    .find(|field| field.column.name == e.column())
+                {
+                    field.error = Some(e.to_string());
+                }
+            }
+        }
+
+        match self.fields.iter().position(|field| field.error.is_some()) {
+            Some(index) => {
+                self.focus(index);
+                false
+            }
+            None => true,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        let index = self.editable_index(self.focused + 1, 1);
+        self.focus(index);
+    }
+
+    pub fn previous_field(&mut self) {
+        let index = self.editable_index(self.focused + self.fields.len() - 1, -1);
+        self.focus(index);
+    }
+
+    /// First editable field from `start`, walking in `step` direction and wrapping around.
+    fn editable_index(&self, start: usize, step: isize) -> usize {
+        let len = self.fields.len();
+        (0..len)
+            .map(|offset| {
+                (start as isize + step * offset as isize).rem_euclid(len as isize) as usize
+            })
+            .find(|index| self.fields[*index].column.editable)
+            .unwrap_or(self.focused)
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.fields.iter().any(FormField::is_changed)
+    }
+
+    fn focus(&mut self, index: usize) {
+        self.focused = index;
+        self.cursor = self.focused_len();
+    }
+
+    fn focused_len(&self) -> usize {
+        self.fields[self.focused].value.chars().count()
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        let field = &mut self.fields[self.focused];
+        let index = byte_index(&field.value, self.cursor);
+        field.value.insert(index, new_char);
+        field.error = None;
+        self.cursor += 1;
+    }
+
+    pub fn delete_char(&mut self) {
+        if self.cursor == 0 {
+            return;
+        }
+
+        let field = &mut self.fields[self.focused];
+        let before = field.value.chars().take(self.cursor - 1);
+        let after = field.value.chars().skip(self.cursor);
+        field.value = before.chain(after).collect();
+        field.error = None;
+        self.cursor -= 1;
+    }
+
+    pub fn move_cursor_left(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    pub fn move_cursor_right(&mut self) {
+        self.cursor = (self.cursor + 1).min(self.focused_len());
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let area = popup_area(frame.area(), 60, 80);
+        frame.render_widget(Clear, area);
+
+        let block = Block::bordered()
+            .title(self.title.as_str())
+            .title_bottom("(Tab/↓) 下一项 | (↑) 上一项 | (Enter) 保存 | (Esc) 取消");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let label_width = self
+            .fields
+            .iter()
+            .map(|field| field.column.name.width())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines: Vec<Line> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let style = if i == self.focused {
+                    Style::default().fg(Color::Yellow)
+                } else if !field.column.editable {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                let marker = if field.is_changed() { "*" } else { " " };
+                let mut spans = vec![
+                    Span::styled(
+                        format!("{}{:>label_width$} : ", marker, field.column.name),
+                        style,
+                    ),
+                    Span::styled(field.value.as_str(), style),
+                ];
+                if let Some(error) = &field.error {
+                    spans.push(Span::raw("  "));
+                    spans.push(error.as_str().red());
+                }
+                Line::from(spans)
+            })
+            .collect();
+
+        if self.has_changes() {
+            lines.push(Line::default());
+            lines.push(Line::from("待保存修改:"));
+            for field in self.fields.iter().filter(|field| field.is_changed()) {
+                lines.push(Line::from(vec![
+                    Span::raw(format!("  {} : ", field.column.name)),
+                    field.original.as_deref().unwrap_or_default().red(),
+                    Span::raw(" → "),
+                    field.value.as_str().green(),
+                ]));
+            }
+        }
+
+        if let Some(error) = &self.error {
+            lines.push(Line::default());
+            lines.push(Line::from(error.as_str()).red());
+        }
+
+        frame.render_widget(Paragraph::new(lines), inner);
+
+        let before_cursor: String = self.fields[self.focused]
+            .value
+            .chars()
+            .take(self.cursor)
+            .collect();
+        #[allow(clippy::cast_possible_truncation)]
+        frame.set_cursor_position(Position::new(
+            inner.x + (label_width + 4 + before_cursor.width()) as u16,
+            inner.y + self.focused as u16,
+        ));
+    }
+}
+
+fn byte_index(value: &str, char_index: usize) -> usize {
+    value
+        .char_indices()
+        .map(|(i, _)| i)
+        .nth(char_index)
+        .unwrap_or(value.len())
+}
@@ -0,0 +1,100 @@
+use ratatui::{
+    style::Style,
+    text::{Line, Span},
+};
+
+/// Char positions of `text` matched by `query`, ignoring case.
+///
+/// A run of consecutive chars is preferred; otherwise the chars of `query`
+/// only have to appear in order, so `wha` and `wle` both find `whale`.
+pub fn find(query: &str, text: &str) -> Option<Vec<usize>> {
+    let query: Vec<char> = query.chars().map(lowercase).collect();
+    let text: Vec<char> = text.chars().map(lowercase).collect();
+    if query.is_empty() {
+        return Some(vec![]);
+    }
+
+    if let Some(start) = text
+        .windows(query.len())
+        .position(|window| window == query.as_slice())
+    {
+        return Some((start..start + query.len()).collect());
+    }
+
+    let mut positions = Vec::with_capacity(query.len());
+    let mut wanted = query.iter().peekable();
+    for (i, c) in text.iter().enumerate() {
+        if wanted.peek() == Some(&c) {
+            positions.push(i);
+            wanted.next();
+        }
+    }
+    wanted.peek().is_none().then_some(positions)
+}
+
+/// One char standing for `c` lowercased, so positions stay aligned with `text`.
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// `text` with the chars at `positions` drawn in `style`.
+pub fn highlight(text: &str, positions: &[usize], style: Style) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = vec![];
+    let mut run = String::new();
+    let mut run_matched = false;
+
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            spans.push(span(std::mem::take(&mut run), run_matched, style));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(span(run, run_matched, style));
+    }
+    Line::from(spans)
+}
+
+fn span(content: String, matched: bool, style: Style) -> Span<'static> {
+    if matched {
+        Span::styled(content, style)
+    } else {
+        Span::raw(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_run_of_chars_is_preferred() {
+        assert_eq!(find("wha", "whale"), Some(vec![0, 1, 2]));
+        assert_eq!(find("le", "lxle"), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn scattered_chars_match_in_order() {
+        assert_eq!(find("wle", "whale"), Some(vec![0, 3, 4]));
+        assert_eq!(find("elw", "whale"), None);
+    }
+
+    #[test]
+    fn case_is_ignored() {
+        assert_eq!(find("WH", "whale"), Some(vec![0, 1]));
+        assert_eq!(find("wh", "WHALE"), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn positions_count_chars_not_bytes() {
+        assert_eq!(find("运行", "1 运行"), Some(vec![2, 3]));
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(find("", "whale"), Some(vec![]));
+        assert_eq!(find("w", ""), None);
+    }
+}
@@ -1,43 +1,80 @@
-use fakeit::address::info;
-use lazy_static::lazy_static;
+use chrono::NaiveDateTime;
 use log::info;
-use rust_decimal::Decimal;
-use sqlx::{MySql, MySqlPool, Pool};
-use std::{env, fmt::format, path::Path, str::FromStr, sync::Mutex, time::Duration};
+use std::{
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 use tokio::sync::mpsc;
 
-use color_eyre::{
-    owo_colors::{colors::xterm::Corn, OwoColorize},
-    Result,
-};
+use color_eyre::Result;
 use crossterm::{
     cursor::Show,
     event::{KeyEvent, KeyModifiers},
 };
-use itertools::Itertools;
 use ratatui::{
     crossterm::event::{self, Event, KeyCode, KeyEventKind},
     layout::{Constraint, Flex, Layout, Margin, Position, Rect},
     style::{self, Color, Modifier, Style, Stylize},
-    symbols::border,
     text::{Line, Span, Text},
     widgets::{
         Block, BorderType, Borders, Cell, Clear, HighlightSpacing, Padding, Paragraph, Row,
         Scrollbar, ScrollbarOrientation, ScrollbarState, Table, TableState, Wrap,
     },
-    DefaultTerminal, Frame,
+    Frame,
 };
-use serde::{Deserialize, Serialize};
 use style::palette::tailwind;
+
 use unicode_width::UnicodeWidthStr;
 
-use tokio::{sync::RwLock, time::sleep};
+mod action;
+use action::{Actions, Finished, Outcome, Retry};
+
+mod cli;
+
+mod config;
+use config::{Config, CONFIG_FILE, LOG_CONFIG_FILE};
+
+mod conflict;
+use conflict::Conflict;
 
 mod db;
-use db::dbutils::{Database, DexVolumeTask, MmVolumeTask};
+use db::dbutils::{Database, Expected, Page, Sort, TaskPage};
+use db::error::DbError;
+use db::filter::Filter;
+use db::model::{DexVolumeTask, MmVolumeTask};
+use db::schema::TaskTable;
+
+mod diff;
+use diff::Tally;
+
+mod environment;
+use environment::{badge_style, Connection, EnvMenu};
+
+mod display;
+use display::{column_widths, TaskRow};
+
+mod error;
+use error::AppError;
+
+mod form;
+use form::{Form, FormMode};
+
+mod fuzzy;
+
+mod preset;
+use preset::{MenuChoice, Preset, PresetMenu, Presets};
+
+mod refresh;
+use refresh::{Backoff, Newest, Refresh};
+
+mod status;
+use status::Status;
+
+mod view;
+use view::{Query, View};
 // use db::dbutils::Database::get_all_users;
-use futures::{executor::block_on, future::ok};
-use log4rs;
 
 const PALETTES: [tailwind::Palette; 4] = [
     tailwind::BLUE,
@@ -46,55 +83,25 @@ const PALETTES: [tailwind::Palette; 4] = [
     tailwind::RED,
 ];
 const INFO_TEXT: [&str; 2] = [
-    "(Q) quit | (Tab) Switch work space | (↑) move up | (↓) move down | (←) move left | (→) move right",
-    "(Shift + →) next color | (Shift + ←) previous color",
+    "(Q) quit | (Tab) Switch work space | (↑) move up | (↓) move down | (←) move left | (→) move right | (S) sort by column | (/) quick filter",
+    "(Shift + →) next color | (Shift + ←) previous color | (N) new task | (E) edit task | (Y) copy task | (D) delete task | (R) refresh now | (P) pause refresh",
 ];
 
 const ITEM_HEIGHT: usize = 4;
 
-lazy_static! {
-    static ref GLOBAL_MM_ARRAY: Mutex<Vec<MmVolumeTask>> = Mutex::new(vec![]);
-}
+/// Lines drawn for every table row.
+const ROW_HEIGHT: u16 = 3;
 
-lazy_static! {
-    static ref GLOBAL_DEX_ARRAY: Mutex<Vec<DexVolumeTask>> = Mutex::new(vec![]);
-}
+/// Width of the spinner and label shown under the id of a row being saved.
+const ROW_STATUS_WIDTH: u16 = 9;
 
-lazy_static! {
-    static ref SEARCH_KEY_WORD: Mutex<String> = Mutex::new("".to_string());
-}
-// static GLOBAL_DATA = new vec();
+/// Rows per page until the table has been drawn and its height is known.
+const DEFAULT_PAGE_SIZE: u64 = 20;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Config {
-    db_server: String,
-    db_port: u16,
-    db_user: String,
-    db_password: String,
-    db_name: String,
-    enable_logging: bool,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            db_server: "http://localhost".to_string(),
-            db_port: 3306,
-            db_user: "root".to_string(),
-            db_password: "password".to_string(),
-            db_name: "db".to_string(),
-            enable_logging: true,
-        }
-    }
-}
+/// How long the table height must hold still before the page is fetched at the new size.
+const RESIZE_SETTLE: Duration = Duration::from_millis(300);
 
-fn load_config() -> Config {
-    confy::load_path("/opt/xtool/config.toml").expect("Failed to load config")
-}
-
-fn is_numeric(s: &str) -> bool {
-    Decimal::from_str(s).is_ok()
-}
+// static GLOBAL_DATA = new vec();
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -103,127 +110,176 @@ async fn main() -> Result<()> {
     // env::set_var("RUST_LOG", "info");
     // env_logger::init();
 
-    log4rs::init_file("/opt/xtool/log4rs.yaml", Default::default()).unwrap();
-
-    let config_file_path = "/opt/xtool/config.toml";
-
-    // check if config file exists
-    if !Path::new(&config_file_path).exists() {
-        eprintln!(
-            "Error: Configuration file does not exist at {:?}",
-            config_file_path
-        );
-        std::process::exit(1);
-    }
-
-    let config: Config = load_config();
+    color_eyre::install()?;
 
-    let db_url = format!(
-        "mysql://{}:{}@{}:{}/{}",
-        config.db_user, config.db_password, config.db_server, config.db_port, config.db_name
-    );
-    let db: Database = Database::new(&db_url).await.unwrap();
+    let matches = cli::command().get_matches();
 
-    let db1: Database = Database::new(&db_url).await.unwrap();
+    let config_path = config::find(
+        matches.get_one::<PathBuf>("config").map(PathBuf::as_path),
+        CONFIG_FILE,
+    )?;
+    let config = Config::load(&config_path)?;
 
-    let mm_volumes: &Vec<MmVolumeTask> = &db.get_all_mm_volume_task("").await.unwrap();
-    {
-        let mut _datas: Vec<MmVolumeTask> = vec![];
-        let mut array = GLOBAL_MM_ARRAY.lock().unwrap();
-        for _temp in mm_volumes {
-            array.push(_temp.clone());
-        }
-    }
-
-    let dex_volumes: &Vec<DexVolumeTask> = &db.get_all_dex_volume_task("").await.unwrap();
-    {
-        let mut _datas: Vec<DexVolumeTask> = vec![];
-        let mut array = GLOBAL_DEX_ARRAY.lock().unwrap();
-        for _temp in dex_volumes {
-            array.push(_temp.clone());
-        }
-    }
+    if config.enable_logging {
+        let log_config_path = config::find(
+            matches
+                .get_one::<PathBuf>("log-config")
+                .map(PathBuf::as_path),
+            LOG_CONFIG_FILE,
+        )?;
+        log4rs::init_file(&log_config_path, Default::default())
+            .map_err(|e| AppError::Logging(e.to_string()))?;
+    }
+    info!(" config loaded from {} ", config_path.display());
+
+    let env = config.start_environment(matches.get_one::<String>("env").map(String::as_str));
+    let environment = config.environment(&env)?;
+    info!(" connecting to environment {} ", env);
+
+    // one pool per environment, shared by the UI and the refresh tasks
+    let db = Arc::new(Database::new(&environment.database_url()).await?);
+
+    // a subcommand runs without the TUI, for scripts and cron
+    if matches.subcommand().is_some() {
+        return Ok(cli::run(&db, &matches).await?);
+    }
+
+    // a broken presets file only costs the presets, not the session
+    let (presets, presets_error) = match Presets::load(&config_path) {
+        Ok(presets) => (presets, None),
+        Err(e) => (Presets::unavailable(), Some(e)),
+    };
+
+    let mm_volumes = db
+        .get_mm_volume_task_page(
+            &Filter::default(),
+            None,
+            Page::first(DEFAULT_PAGE_SIZE),
+            None,
+        )
+        .await?;
+    let dex_volumes = db
+        .get_dex_volume_task_page(
+            &Filter::default(),
+            None,
+            Page::first(DEFAULT_PAGE_SIZE),
+            None,
+        )
+        .await?;
 
-    color_eyre::install()?;
     let mut terminal = ratatui::init();
-    let mut app = App::new();
-
     let (tx, mut rx) = mpsc::channel(32);
     let (tx1, mut rx1) = mpsc::channel(32);
+    let (action_tx, mut action_rx) = mpsc::channel(32);
+
+    let mm_interval = config.refresh_interval(TaskTable::Mm);
+    let dex_interval = config.refresh_interval(TaskTable::Dex);
+    let connection = Connection {
+        env,
+        production: environment.production,
+        db,
+    };
+    let mut app = App::new(
+        connection,
+        config,
+        action_tx,
+        presets,
+        mm_volumes,
+        dex_volumes,
+    );
+    if let Some(e) = presets_error {
+        info!(" presets not loaded : {} ", e);
+        app.status.error(e.to_string());
+    }
 
+    let mm_connection = app.shared_connection();
+    let mm_query = app.mm_view.shared();
+    let mm_trigger = app.mm_view.trigger();
     tokio::spawn(async move {
+        let mut backoff = Backoff::new();
+        let mut newest = Newest::default();
         loop {
-            let key_word;
-
+            let query = mm_query.lock().unwrap().clone();
+            let connection = mm_connection.lock().unwrap().clone();
+
+            // a failing query is retried sooner, backing off while the database stays away
+            let since = newest.since(&connection.env, &query.filter);
+            let (refresh, wait) = match connection
+                .db
+                .get_mm_volume_task_page(&query.filter, query.sort, query.page, since)
+                .await
             {
-                let _key_word: std::sync::MutexGuard<'_, String> = SEARCH_KEY_WORD.lock().unwrap();
-                key_word = _key_word.clone();
-            }
-
-            match db.get_all_mm_volume_task(&key_word).await {
                 Ok(tasks) => {
-                    let mut datas = Vec::new();
-                    for _temp in tasks {
-                        datas.push(_temp);
-                    }
-
-                    {
-                        let mut array = GLOBAL_MM_ARRAY.lock().unwrap();
-                        *array = datas.clone();
-                    }
-
-                    if tx.send(datas).await.is_err() {
-                        break;
-                    }
+                    backoff.reset();
+                    newest.saw(&connection.env, &query.filter, tasks.newest);
+                    (Refresh::Loaded(tasks), mm_interval)
                 }
-                Err(e) => eprintln!("Failed to fetch data: {:?}", e),
+                Err(error) => {
+                    info!(" mm refresh failed : {} ", error);
+                    let retry_in = backoff.next_delay();
+                    (Refresh::Failed { error, retry_in }, retry_in)
+                }
+            };
+
+            // tagged with the environment and query, so rows of one left behind are dropped
+            if tx.send((connection.env, query, refresh)).await.is_err() {
+                break;
             }
 
-            sleep(Duration::from_secs(10)).await;
+            mm_trigger.wait(wait).await;
         }
     });
 
+    let dex_connection = app.shared_connection();
+    let dex_query = app.dex_view.shared();
+    let dex_trigger = app.dex_view.trigger();
     tokio::spawn(async move {
+        let mut backoff = Backoff::new();
+        let mut newest = Newest::default();
+        // stagger the two loops so they do not hit the database together
+        dex_trigger.wait(dex_interval / 2).await;
         loop {
-            sleep(Duration::from_secs(5)).await;
-            let key_word;
-
+            let query = dex_query.lock().unwrap().clone();
+            let connection = dex_connection.lock().unwrap().clone();
+
+            let since = newest.since(&connection.env, &query.filter);
+            let (refresh, wait) = match connection
+                .db
+                .get_dex_volume_task_page(&query.filter, query.sort, query.page, since)
+                .await
             {
-                let _key_word = SEARCH_KEY_WORD.lock().unwrap();
-                key_word = _key_word.clone();
-            }
-
-            match db1.get_all_dex_volume_task(&key_word).await {
                 Ok(tasks) => {
-                    let mut datas = Vec::new();
-                    for _temp in tasks {
-                        datas.push(_temp);
-                    }
-
-                    {
-                        let mut array = GLOBAL_DEX_ARRAY.lock().unwrap();
-                        *array = datas.clone();
-                    }
-
-                    if tx1.send(datas).await.is_err() {
-                        break;
-                    }
+                    backoff.reset();
+                    newest.saw(&connection.env, &query.filter, tasks.newest);
+                    (Refresh::Loaded(tasks), dex_interval)
+                }
+                Err(error) => {
+                    info!(" dex refresh failed : {} ", error);
+                    let retry_in = backoff.next_delay();
+                    (Refresh::Failed { error, retry_in }, retry_in)
                 }
-                Err(e) => eprintln!("Failed to fetch data: {:?}", e),
+            };
+
+            if tx1.send((connection.env, query, refresh)).await.is_err() {
+                break;
             }
 
-            sleep(Duration::from_secs(5)).await;
+            dex_trigger.wait(wait).await;
         }
     });
 
     loop {
         tokio::select! {
-            Some(mm_data) = rx.recv() => {
-                app.refresh_mm_data(mm_data);
+            Some((env, query, mm_data)) = rx.recv() => {
+                app.refresh_mm_data(&env, query, mm_data);
             }
 
-            Some(dex_data) = rx1.recv() => {
-                app.refresh_dex_data(dex_data);
+            Some((env, query, dex_data)) = rx1.recv() => {
+                app.refresh_dex_data(&env, query, dex_data);
+            }
+
+            Some(finished) = action_rx.recv() => {
+                app.finish_action(finished);
             }
 
             result = async {
@@ -252,16 +308,28 @@ async fn main() -> Result<()> {
                                 let _ = app.exit_program();
                             }
 
+                            KeyCode::Char('s') => app.toggle_sort(),
 
-                            KeyCode::Down => match app.focus_area {
-                                FocusArea::Lower => app.next_row(),
-                                _ => {},
-                            },
+                            KeyCode::Char('/') => app.start_quick_filter(),
 
-                            KeyCode::Up => match app.focus_area {
-                                FocusArea::Lower => app.previous_row(),
-                                _ => {},
-                            },
+                            KeyCode::Char('r') => app.refresh_now(),
+
+                            KeyCode::Char('p') => app.toggle_refresh_pause(),
+
+                            KeyCode::Char(c) => {
+                                if let Some(action) = RowAction::from_key(c) {
+                                    app.run_action(action);
+                                }
+                            }
+
+
+                            KeyCode::Down if app.focus_area == FocusArea::Lower => app.next_row(),
+
+                            KeyCode::Up if app.focus_area == FocusArea::Lower => app.previous_row(),
+
+                            KeyCode::PageDown => app.next_page(),
+
+                            KeyCode::PageUp => app.previous_page(),
 
                             KeyCode::Right if shift_pressed => app.next_color(),
                             KeyCode::Left if shift_pressed => {
@@ -295,6 +363,42 @@ async fn main() -> Result<()> {
                             KeyCode::Esc => app.cancel_edit(),
                             _ => {}
                         },
+                        InputMode::Form if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.submit_form(),
+                            KeyCode::Esc => app.cancel_form(),
+                            code => app.edit_form(code),
+                        },
+                        InputMode::Confirm if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.confirm_action(),
+                            KeyCode::Esc => app.cancel_action(),
+                            _ => {}
+                        },
+                        InputMode::QuickFilter if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.keep_quick_filter(),
+                            KeyCode::Esc => app.clear_quick_filter(),
+                            KeyCode::Char(c) => app.quick_filter_char(c),
+                            KeyCode::Backspace => app.quick_filter_backspace(),
+                            KeyCode::Down => app.next_row(),
+                            KeyCode::Up => app.previous_row(),
+                            _ => {}
+                        },
+                        InputMode::Presets if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.choose_preset(),
+                            KeyCode::Esc => app.cancel_preset_menu(),
+                            code => app.edit_preset_menu(code),
+                        },
+                        InputMode::Environments if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.choose_environment(),
+                            KeyCode::Esc => app.close_env_menu(),
+                            KeyCode::Down | KeyCode::Tab => app.next_environment(),
+                            KeyCode::Up | KeyCode::BackTab => app.previous_environment(),
+                            _ => {}
+                        },
+                        InputMode::Conflict if key.kind == KeyEventKind::Press => match key.code {
+                            KeyCode::Enter => app.overwrite_conflict(),
+                            KeyCode::Esc => app.discard_conflict(),
+                            _ => {}
+                        },
                         _ => {}
                     }
                 }
@@ -302,12 +406,13 @@ async fn main() -> Result<()> {
         }
 
         // 渲染 UI
-        terminal.draw(|frame| app.draw(frame))?;
-    }
+        if let Err(e) = terminal.draw(|frame| app.draw(frame)) {
+            ratatui::restore();
+            return Err(AppError::from(e).into());
+        }
 
-    ratatui::restore();
-    Ok(())
-    // app_result
+        app.reload_if_resized();
+    }
 }
 struct TableColors {
     buffer_bg: Color,
@@ -356,254 +461,70 @@ struct SelectedCell {
     cell_value: String,
 }
 
-impl MmVolumeTask {
-    const fn ref_array(&self) -> [&String; 19] {
-        [
-            &self.id,
-            &self.launch_id,
-            &self.token_add,
-            &self.target_volume,
-            &self.do_status,
-            &self.use_wallet_type,
-            &self.remark,
-            &self.buy_rate,
-            &self.buy_per_low,
-            &self.buy_per_high,
-            &self.sell_percent,
-            &self.frequent_low,
-            &self.frequent_high,
-            &self.real_sol,
-            &self.create_time,
-            &self.update_time,
-            &self.col1,
-            &self.col2,
-            &self.col3,
-        ]
-    }
-
-    fn id(&self) -> &str {
-        &self.id
-    }
-
-    fn launch_id(&self) -> &str {
-        &self.launch_id
-    }
-
-    fn token_add(&self) -> &str {
-        &self.token_add
-    }
-
-    fn target_volume(&self) -> &str {
-        &self.target_volume
-    }
-
-    fn do_status(&self) -> &str {
-        &self.do_status
-    }
-
-    fn use_wallet_type(&self) -> &str {
-        &self.use_wallet_type
-    }
-
-    fn remark(&self) -> &str {
-        &self.remark
-    }
-
-    fn buy_rate(&self) -> &str {
-        &self.buy_rate
-    }
-
-    fn buy_per_low(&self) -> &str {
-        &self.buy_per_low
-    }
-
-    fn buy_per_high(&self) -> &str {
-        &self.buy_per_high
-    }
-
-    fn sell_percent(&self) -> &str {
-        &self.sell_percent
-    }
-
-    fn frequent_low(&self) -> &str {
-        &self.frequent_low
-    }
-
-    fn frequent_high(&self) -> &str {
-        &self.frequent_high
-    }
-
-    fn real_sol(&self) -> &str {
-        &self.real_sol
-    }
-
-    fn create_time(&self) -> &str {
-        &self.create_time
-    }
-
-    fn update_time(&self) -> &str {
-        &self.update_time
-    }
-
-    // fn col(&self) -> &str {
-    //     &self.col
-    // }
+#[derive(PartialEq)]
+enum InputMode {
+    Normal,
+    Editing,
+    Form,
+    Confirm,
+    Presets,
+    QuickFilter,
+    Environments,
+    Conflict,
 }
 
-impl DexVolumeTask {
-    const fn ref_array(&self) -> [&String; 19] {
-        [
-            &self.id,
-            &self.pool_id,
-            &self.token_add,
-            &self.mm_type,
-            &self.remark,
-            &self.target_price,
-            &self.stop_price_per,
-            &self.do_status,
-            &self.buy_rate,
-            &self.buy_per_low,
-            &self.buy_per_high,
-            &self.sell_percent,
-            &self.frequent_low,
-            &self.frequent_high,
-            &self.bsdiff,
-            &self.create_time,
-            &self.update_time,
-            &self.copy,
-            &self.del,
-        ]
-    }
-
-    fn id(&self) -> &str {
-        &self.id
-    }
-
-    fn pool_id(&self) -> &str {
-        &self.pool_id
-    }
-
-    fn token_add(&self) -> &str {
-        &self.token_add
-    }
-
-    fn mm_type(&self) -> &str {
-        &self.mm_type
-    }
-
-    fn remark(&self) -> &str {
-        &self.remark
-    }
-
-    fn target_price(&self) -> &str {
-        &self.target_price
-    }
-
-    fn stop_price_per(&self) -> &str {
-        &self.stop_price_per
-    }
-
-    fn do_status(&self) -> &str {
-        &self.do_status
-    }
-
-    fn buy_rate(&self) -> &str {
-        &self.buy_rate
-    }
-
-    fn buy_per_low(&self) -> &str {
-        &self.buy_per_low
-    }
-
-    fn buy_per_high(&self) -> &str {
-        &self.buy_per_high
-    }
-
-    fn sell_percent(&self) -> &str {
-        &self.sell_percent
-    }
-
-    fn frequent_low(&self) -> &str {
-        &self.frequent_low
-    }
-
-    fn frequent_high(&self) -> &str {
-        &self.frequent_high
-    }
-
-    fn bsdiff(&self) -> &str {
-        &self.bsdiff
-    }
-
-    fn create_time(&self) -> &str {
-        &self.create_time
-    }
-
-    fn update_time(&self) -> &str {
-        &self.update_time
-    }
+/// An operation on a whole row, bound to a key in Normal mode.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum RowAction {
+    New,
+    Edit,
+    Copy,
+    Delete,
+}
 
-    fn copy(&self) -> &str {
-        &self.copy
+impl RowAction {
+    fn from_key(key: char) -> Option<Self> {
+        match key {
+            'n' => Some(RowAction::New),
+            'e' => Some(RowAction::Edit),
+            'y' => Some(RowAction::Copy),
+            'd' => Some(RowAction::Delete),
+            _ => None,
+        }
     }
 
-    fn del(&self) -> &str {
-        &self.del
+    /// Question asked before the action runs, for actions that need confirming.
+    fn prompt(&self) -> Option<&'static str> {
+        match self {
+            RowAction::New | RowAction::Edit => None,
+            RowAction::Copy => Some("是否复制本行, 确定请按回车, 取消按ESC"),
+            RowAction::Delete => Some("是否删除本行, 确定请按回车, 取消按ESC"),
+        }
     }
 }
 
-#[derive(PartialEq)]
-enum InputMode {
-    Normal,
-    Editing,
-}
-
 struct App {
+    db: Arc<Database>,
+    /// Environment `db` belongs to, also read by the refresh tasks.
+    connection: Arc<Mutex<Connection>>,
+    env: String,
+    production: bool,
+    config: Config,
+    env_menu: Option<EnvMenu>,
+    actions: Actions,
+    status: Status,
     state: TableState,
     mm_items: Vec<MmVolumeTask>,
     dex_items: Vec<DexVolumeTask>,
-    longest_mm_item_lens: (
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-    ), // order is (name, address, email)
-    longest_dex_item_lens: (
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-        u16,
-    ), // order is
+    /// Query, search and table position of each view.
+    mm_view: View,
+    dex_view: View,
+    /// Rows that fit in the table, the limit of every page query.
+    page_size: u64,
+    /// When the table height last changed, until the page is fetched at the new size.
+    resized_at: Option<Instant>,
+    longest_mm_item_lens: Vec<u16>,
+    longest_dex_item_lens: Vec<u16>,
     scroll_state: ScrollbarState,
     colors: TableColors,
     color_index: usize,
@@ -613,13 +534,28 @@ struct App {
     character_index: usize,
     editing_key: String,
     editing_key_value: String,
+    /// `update_time` of the row when its cell editor opened.
+    editing_version: Option<NaiveDateTime>,
     editing_column: String,
+    /// Value of the cell when its editor opened.
+    editing_original: String,
+    /// Why the last submitted cell value or row action was refused.
+    input_error: Option<String>,
+    /// Row action awaiting confirmation, with the id of its row.
+    pending_action: Option<(RowAction, String)>,
     focus_area: FocusArea,
     header_item_selected_index: u8,
     header_item_max_index: u8,
-    search_word: String,
     search_word_character_index: usize,
+    /// Why the submitted search text is not a valid filter.
+    search_error: Option<String>,
+    presets: Presets,
+    preset_menu: Option<PresetMenu>,
     working_data: WorkingData,
+    form: Option<Form>,
+    /// Whether the background refresh of both views is on hold.
+    refresh_paused: bool,
+    conflict: Option<Conflict>,
 }
 
 #[derive(PartialEq, Debug)]
@@ -634,6 +570,32 @@ enum WorkingData {
     Dex,
 }
 
+impl WorkingData {
+    fn table(&self) -> TaskTable {
+        match self {
+            WorkingData::Mm => TaskTable::Mm,
+            WorkingData::Dex => TaskTable::Dex,
+        }
+    }
+}
+
+fn selected_cell<T: TaskRow>(items: &[&T], row: usize, column: usize) -> Option<SelectedCell> {
+    let data = items.get(row)?;
+    let cell_name = T::COLUMNS.get(column)?;
+
+    Some(SelectedCell {
+        key_name: "id".to_string(),
+        key_value: data.id().to_string(),
+        cell_name: cell_name.to_string(),
+        cell_value: data.values().swap_remove(column),
+    })
+}
+
+fn row_values<T: TaskRow>(items: &[&T], row: usize) -> Option<Vec<(&'static str, String)>> {
+    let data = items.get(row)?;
+    Some(T::COLUMNS.iter().copied().zip(data.values()).collect())
+}
+
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -643,56 +605,306 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     area
 }
 
-impl App {
-    fn new() -> Self {
-        let mm_array: std::sync::MutexGuard<'_, Vec<MmVolumeTask>> =
-            GLOBAL_MM_ARRAY.lock().unwrap();
-        let mm_data_vec = mm_array.clone();
+/// Draw `lines` in `block` hanging from `anchor`, as wide as the widest line
+/// and clipped to the frame. Returns the area inside the border.
+fn render_dropdown(frame: &mut Frame, anchor: Rect, lines: Vec<Line>, block: Block) -> Rect {
+    let width = lines
+        .iter()
+        .map(Line::width)
+        .max()
+        .unwrap_or(0)
+        .max(anchor.width as usize);
+    let area = frame.area();
+    #[allow(clippy::cast_possible_truncation)]
+    let dropdown = Rect::new(
+        anchor.x,
+        anchor.y + anchor.height,
+        (width as u16 + 2).min(area.width - anchor.x),
+        (lines.len() as u16 + 2).min(area.height - anchor.y - anchor.height),
+    );
+    frame.render_widget(Clear, dropdown);
 
-        let dex_array: std::sync::MutexGuard<'_, Vec<DexVolumeTask>> =
-            GLOBAL_DEX_ARRAY.lock().unwrap();
-        let dex_data_vec = dex_array.clone();
+    let inner = block.inner(dropdown);
+    frame.render_widget(Paragraph::new(lines).block(block), dropdown);
+    inner
+}
+
+impl App {
+    fn new(
+        connection: Connection,
+        config: Config,
+        action_tx: mpsc::Sender<Finished>,
+        presets: Presets,
+        mm_page: TaskPage<MmVolumeTask>,
+        dex_page: TaskPage<DexVolumeTask>,
+    ) -> Self {
+        let mm_data_vec = mm_page.items;
+        let dex_data_vec = dex_page.items;
 
         info!("data_vec {:?} ", mm_data_vec);
 
+        let scroll_state = |len: usize| ScrollbarState::new(len.saturating_sub(1) * ITEM_HEIGHT);
+        let mut dex_view = View::new(dex_page.page, dex_page.total, dex_page.newest);
+        dex_view.scroll_state = scroll_state(dex_data_vec.len());
+
         Self {
+            db: Arc::clone(&connection.db),
+            env: connection.env.clone(),
+            production: connection.production,
+            connection: Arc::new(Mutex::new(connection)),
+            config,
+            env_menu: None,
+            actions: Actions::new(action_tx),
+            status: Status::default(),
             state: TableState::default().with_selected(0),
-            longest_mm_item_lens: constraint_mm_len_calculator(&mm_data_vec),
-            longest_dex_item_lens: constraint_dex_len_calculator(&dex_data_vec),
-            scroll_state: ScrollbarState::new(
-                (if mm_data_vec.len() > 1 {
-                    mm_data_vec.len() - 1
-                } else {
-                    0
-                }) * ITEM_HEIGHT,
-            ),
+            longest_mm_item_lens: column_widths(&mm_data_vec),
+            longest_dex_item_lens: column_widths(&dex_data_vec),
+            scroll_state: scroll_state(mm_data_vec.len()),
             colors: TableColors::new(&PALETTES[0]),
             color_index: 0,
             mm_items: mm_data_vec,
             dex_items: dex_data_vec,
+            mm_view: View::new(mm_page.page, mm_page.total, mm_page.newest),
+            dex_view,
+            page_size: DEFAULT_PAGE_SIZE,
+            resized_at: None,
             show_popup: false,
             input: String::new(),
             input_mode: InputMode::Normal,
             character_index: 0,
             editing_key: String::new(),
             editing_key_value: String::new(),
+            editing_version: None,
             editing_column: String::new(),
+            editing_original: String::new(),
+            input_error: None,
+            pending_action: None,
             focus_area: FocusArea::Lower,
             header_item_selected_index: 0,
-            header_item_max_index: 1,
+            header_item_max_index: 3,
             // search_input_mode: InputMode::Normal,
-            search_word: String::new(),
             search_word_character_index: 0,
+            search_error: None,
+            presets,
+            preset_menu: None,
             working_data: WorkingData::Mm,
+            form: None,
+            conflict: None,
+            refresh_paused: false,
+        }
+    }
+
+    pub fn refresh_mm_data(&mut self, env: &str, query: Query, refresh: Refresh<MmVolumeTask>) {
+        if env != self.env {
+            return;
+        }
+
+        match refresh {
+            Refresh::Loaded(data) => {
+                if self.mm_view.is_refresh(&query) {
+                    let tally = self.mm_view.changes.record(
+                        &self.mm_items,
+                        self.mm_view.total,
+                        self.mm_view.newest,
+                        &data,
+                    );
+                    self.report_tally(TaskTable::Mm, &tally);
+                }
+                self.apply_mm_page(query, data);
+                self.mm_view.refresh_failed = false;
+                self.status.refresh_succeeded(TaskTable::Mm);
+            }
+            Refresh::Failed { error, retry_in } => {
+                self.mm_view.refresh_failed = true;
+                self.status.refresh_failed(TaskTable::Mm, &error, retry_in)
+            }
+        }
+    }
+
+    pub fn refresh_dex_data(&mut self, env: &str, query: Query, refresh: Refresh<DexVolumeTask>) {
+        if env != self.env {
+            return;
+        }
+
+        match refresh {
+            Refresh::Loaded(data) => {
+                if self.dex_view.is_refresh(&query) {
+                    let tally = self.dex_view.changes.record(
+                        &self.dex_items,
+                        self.dex_view.total,
+                        self.dex_view.newest,
+                        &data,
+                    );
+                    self.report_tally(TaskTable::Dex, &tally);
+                }
+                self.apply_dex_page(query, data);
+                self.dex_view.refresh_failed = false;
+                self.status.refresh_succeeded(TaskTable::Dex);
+            }
+            Refresh::Failed { error, retry_in } => {
+                self.dex_view.refresh_failed = true;
+                self.status.refresh_failed(TaskTable::Dex, &error, retry_in)
+            }
+        }
+    }
+
+    /// Handle for a refresh task, to fetch from whichever environment is connected.
+    fn shared_connection(&self) -> Arc<Mutex<Connection>> {
+        Arc::clone(&self.connection)
+    }
+
+    fn open_env_menu(&mut self) {
+        let entries = self
+            .config
+            .environment_names()
+            .into_iter()
+            .map(|name| {
+                let production = self
+                    .config
+                    .environment(&name)
+                    .is_ok_and(|environment| environment.production);
+                (name, production)
+            })
+            .collect();
+        self.env_menu = Some(EnvMenu::new(entries, &self.env));
+        self.input_mode = InputMode::Environments;
+    }
+
+    pub fn next_environment(&mut self) {
+        if let Some(menu) = self.env_menu.as_mut() {
+            menu.next();
         }
     }
 
-    pub fn refresh_mm_data(&mut self, data: Vec<MmVolumeTask>) {
-        self.mm_items = data;
+    pub fn previous_environment(&mut self) {
+        if let Some(menu) = self.env_menu.as_mut() {
+            menu.previous();
+        }
     }
 
-    pub fn refresh_dex_data(&mut self, data: Vec<DexVolumeTask>) {
-        self.dex_items = data;
+    pub fn close_env_menu(&mut self) {
+        self.env_menu = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Connect to the environment picked in the menu; the session moves over once it is up.
+    pub fn choose_environment(&mut self) {
+        let Some(name) = self
+            .env_menu
+            .as_ref()
+            .and_then(|menu| menu.choose())
+            .map(str::to_string)
+        else {
+            return;
+        };
+        self.close_env_menu();
+        if name == self.env {
+            return;
+        }
+
+        let environment = match self.config.environment(&name) {
+            Ok(environment) => environment,
+            Err(e) => return self.status.error(e.to_string()),
+        };
+        info!(" connecting to environment {} ", name);
+        self.status.info(format!("connecting to {}", name));
+
+        let table = self.working_data.table();
+        self.actions
+            .spawn(table, None, "连接中…", Retry::None, async move {
+                let db = Database::new(&environment.database_url()).await?;
+                Ok(Outcome::Connected(Connection {
+                    env: name,
+                    production: environment.production,
+                    db: Arc::new(db),
+                }))
+            });
+    }
+
+    /// Work on the database of `connection` from now on, dropping everything tied to the old one.
+    fn switch_environment(&mut self, connection: Connection) {
+        info!(" switched to environment {} ", connection.env);
+        // results of actions on the old database no longer match what is shown
+        self.actions.discard_all();
+        self.form = None;
+        self.conflict = None;
+        self.pending_action = None;
+        self.preset_menu = None;
+        self.show_popup = false;
+        self.input.clear();
+        self.input_error = None;
+        self.input_mode = InputMode::Normal;
+
+        self.status.info(format!("switched to {}", connection.env));
+        self.db = Arc::clone(&connection.db);
+        self.env = connection.env.clone();
+        self.production = connection.production;
+        *self.connection.lock().unwrap() = connection;
+
+        // drop the old rows at once, so no row action or edit can pick an id from
+        // them and send it to the new database before the reload comes back
+        self.mm_items.clear();
+        self.dex_items.clear();
+        self.longest_mm_item_lens = column_widths(&self.mm_items);
+        self.longest_dex_item_lens = column_widths(&self.dex_items);
+        self.select_first_row();
+
+        let shown = self.working_data.table();
+        for table in [TaskTable::Mm, TaskTable::Dex] {
+            let view = self.view_of_mut(table);
+            if table != shown {
+                view.table_state.select(Some(0));
+                view.scroll_state = view.scroll_state.position(0);
+            }
+            view.forget_rows();
+            view.refresh_failed = false;
+            self.status.refresh_succeeded(table);
+            self.load(table, None, "");
+        }
+    }
+
+    /// Fetch the current view at the table height, once it stopped changing.
+    pub fn reload_if_resized(&mut self) {
+        if self
+            .resized_at
+            .is_some_and(|at| at.elapsed() >= RESIZE_SETTLE)
+        {
+            self.resized_at = None;
+            self.load(self.working_data.table(), None, "");
+        }
+    }
+
+    /// Fetch the current view now rather than at its next refresh.
+    pub fn refresh_now(&mut self) {
+        self.view().refresh_now();
+    }
+
+    /// Hold or resume the background refresh of both views.
+    pub fn toggle_refresh_pause(&mut self) {
+        self.refresh_paused = !self.refresh_paused;
+        self.mm_view.set_paused(self.refresh_paused);
+        self.dex_view.set_paused(self.refresh_paused);
+        self.status.info(if self.refresh_paused {
+            "auto refresh paused"
+        } else {
+            "auto refresh resumed"
+        });
+    }
+
+    /// Tell the user rows were inserted or deleted, e.g. by another process.
+    fn report_tally(&mut self, table: TaskTable, tally: &Tally) {
+        let rows = |n: u64| match n {
+            1 => "1 row".to_string(),
+            n => format!("{} rows", n),
+        };
+        let message = match (tally.inserted, tally.deleted) {
+            (0, 0) => return,
+            (inserted, 0) => format!("{} added", rows(inserted)),
+            (0, deleted) => format!("{} removed", rows(deleted)),
+            (inserted, deleted) => format!("{} added, {} removed", rows(inserted), rows(deleted)),
+        };
+        self.status
+            .info(format!("{} in {}", message, table.schema().table));
     }
 
     pub fn exit_program(&mut self) -> Result<()> {
@@ -709,47 +921,418 @@ impl App {
         std::process::exit(0);
     }
 
+    /// Move down a row, continuing on the next page past the last one.
     pub fn next_row(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                let row_length = match self.working_data {
-                    WorkingData::Mm => self.mm_items.len(),
-                    WorkingData::Dex => self.dex_items.len(),
-                };
-
-                if i >= row_length - 1 {
-                    0
-                } else {
-                    i + 1
+            Some(i) if i + 1 < self.row_count() => i + 1,
+            _ => {
+                let (page, total) = self.current_page();
+                if page.number() < page.count(total) {
+                    self.show_page(page.next());
+                } else if page.offset > 0 {
+                    self.show_page(Page::first(page.limit));
                 }
+                0
             }
-            None => 0,
         };
 
         self.state.select(Some(i));
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
 
+    /// Move up a row, continuing on the previous page before the first one.
     pub fn previous_row(&mut self) {
         let i = match self.state.selected() {
-            Some(i) => {
-                let row_length = match self.working_data {
-                    WorkingData::Mm => self.mm_items.len(),
-                    WorkingData::Dex => self.dex_items.len(),
+            Some(i) if i > 0 => i - 1,
+            _ => {
+                let (page, total) = self.current_page();
+                let previous = if page.offset > 0 {
+                    page.previous()
+                } else {
+                    page.last(total)
                 };
-
-                if i == 0 {
-                    row_length - 1
+                if previous == page {
+                    self.row_count().saturating_sub(1)
                 } else {
-                    i - 1
+                    self.show_page(previous);
+                    // clamped to the rows of the page once it is loaded
+                    previous.limit as usize - 1
                 }
             }
-            None => 0,
         };
         self.state.select(Some(i));
         self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
     }
 
+    pub fn next_page(&mut self) {
+        let (page, total) = self.current_page();
+        if page.number() < page.count(total) {
+            self.show_page(page.next());
+            self.state.select(Some(0));
+        }
+    }
+
+    pub fn previous_page(&mut self) {
+        let (page, _) = self.current_page();
+        if page.offset > 0 {
+            self.show_page(page.previous());
+            self.state.select(Some(0));
+        }
+    }
+
+    /// Rows shown in the table, those of the page left by the quick filter.
+    fn row_count(&self) -> usize {
+        match self.working_data {
+            WorkingData::Mm => self.quick_rows(&self.mm_items).len(),
+            WorkingData::Dex => self.quick_rows(&self.dex_items).len(),
+        }
+    }
+
+    /// Rows of `items` matching the quick filter, all of them when it is off.
+    fn quick_rows<'a, T: TaskRow>(&self, items: &'a [T]) -> Vec<&'a T> {
+        self.quick_rows_of(self.working_data.table(), items)
+    }
+
+    /// Rows of `items` matching the quick filter of the view of `table`.
+    fn quick_rows_of<'a, T: TaskRow>(&self, table: TaskTable, items: &'a [T]) -> Vec<&'a T> {
+        let query = &self.view_of(table).quick_filter;
+        items
+            .iter()
+            .filter(|data| {
+                query.is_empty()
+                    || data
+                        .cells()
+                        .iter()
+                        .any(|cell| fuzzy::find(query, cell).is_some())
+            })
+            .collect()
+    }
+
+    pub fn start_quick_filter(&mut self) {
+        self.input_mode = InputMode::QuickFilter;
+        self.focus_area = FocusArea::Lower;
+    }
+
+    pub fn quick_filter_char(&mut self, c: char) {
+        self.view_mut().quick_filter.push(c);
+        self.select_first_row();
+    }
+
+    pub fn quick_filter_backspace(&mut self) {
+        self.view_mut().quick_filter.pop();
+        self.select_first_row();
+    }
+
+    /// Stop typing and keep the table narrowed.
+    pub fn keep_quick_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+    }
+
+    pub fn clear_quick_filter(&mut self) {
+        self.view_mut().quick_filter.clear();
+        self.input_mode = InputMode::Normal;
+        self.select_first_row();
+    }
+
+    fn select_first_row(&mut self) {
+        self.state.select(Some(0));
+        self.scroll_state = self.scroll_state.position(0);
+    }
+
+    /// Page of the current view and the rows its query matches.
+    fn current_page(&self) -> (Page, u64) {
+        let view = self.view();
+        (view.query().page, view.total)
+    }
+
+    fn view(&self) -> &View {
+        self.view_of(self.working_data.table())
+    }
+
+    fn view_mut(&mut self) -> &mut View {
+        self.view_of_mut(self.working_data.table())
+    }
+
+    fn view_of(&self, table: TaskTable) -> &View {
+        match table {
+            TaskTable::Mm => &self.mm_view,
+            TaskTable::Dex => &self.dex_view,
+        }
+    }
+
+    fn view_of_mut(&mut self, table: TaskTable) -> &mut View {
+        match table {
+            TaskTable::Mm => &mut self.mm_view,
+            TaskTable::Dex => &mut self.dex_view,
+        }
+    }
+
+    /// Make `page` the page of `table`, for the UI and the refresh loop.
+    fn set_page(&mut self, table: TaskTable, page: Page) {
+        self.view_of_mut(table).set_page(page);
+    }
+
+    /// Show the other view as it was left: its search, sort, page, selection and scroll.
+    fn switch_view(&mut self) {
+        self.park_table_state();
+        self.working_data = match self.working_data {
+            WorkingData::Mm => WorkingData::Dex,
+            WorkingData::Dex => WorkingData::Mm,
+        };
+        self.park_table_state();
+
+        self.search_word_character_index = self.view().search_word.chars().count();
+        self.search_error = None;
+        self.load(self.working_data.table(), None, "");
+    }
+
+    /// Swap the live table position with the one parked in the current view.
+    ///
+    /// Done once before and once after changing view, which stores the old
+    /// view's position and brings back the new one's.
+    fn park_table_state(&mut self) {
+        let view = match self.working_data {
+            WorkingData::Mm => &mut self.mm_view,
+            WorkingData::Dex => &mut self.dex_view,
+        };
+        std::mem::swap(&mut self.state, &mut view.table_state);
+        std::mem::swap(&mut self.scroll_state, &mut view.scroll_state);
+    }
+
+    /// Sort the current view by the selected column, flipping the direction if it already is.
+    pub fn toggle_sort(&mut self) {
+        let Some(column) = self.state.selected_column() else {
+            return;
+        };
+        let sort = self.view().query().sort;
+        let name = match self.working_data {
+            WorkingData::Mm => MmVolumeTask::COLUMNS.get(column),
+            WorkingData::Dex => DexVolumeTask::COLUMNS.get(column),
+        };
+        let Some(name) = name else {
+            return;
+        };
+
+        let sort = match sort {
+            Some(sort) if sort.column == *name => sort.reversed(),
+            _ => Sort::ascending(name),
+        };
+        self.view_mut().set_sort(Some(sort));
+
+        self.show_page(Page::first(self.page_size));
+        self.state.select(Some(0));
+    }
+
+    /// Empty the search box and drop the filter of the current view.
+    fn clear_search(&mut self) {
+        self.search_word_character_index = 0;
+        self.search_error = None;
+        let view = self.view_mut();
+        view.search_word.clear();
+        view.active_preset = None;
+        view.set_filter(Filter::default());
+    }
+
+    /// Act on the highlighted entry of the preset dropdown.
+    pub fn choose_preset(&mut self) {
+        let Some(choice) = self.preset_menu.as_mut().and_then(PresetMenu::choose) else {
+            return;
+        };
+        self.close_preset_menu();
+
+        let table = self.working_data.table();
+        match choice {
+            MenuChoice::Clear => {
+                self.clear_search();
+                self.show_page(Page::first(self.page_size));
+            }
+            MenuChoice::Apply(preset) => {
+                self.search_word_character_index = preset.filter.chars().count();
+                let parsed = Filter::parse(table.schema(), &preset.filter);
+                self.view_mut().search_word = preset.filter;
+                match parsed {
+                    Ok(filter) => {
+                        self.search_error = None;
+                        let view = self.view_mut();
+                        view.active_preset = Some(preset.name);
+                        view.set_filter(filter);
+                        self.show_page(Page::first(self.page_size));
+                    }
+                    Err(e) => self.search_error = Some(e.to_string()),
+                }
+            }
+            MenuChoice::Save(name) => {
+                let search_word = self.view().search_word.clone();
+                if let Err(e) = Filter::parse(table.schema(), &search_word) {
+                    self.status.error(format!("preset not saved: {}", e));
+                    return;
+                }
+                let preset = Preset {
+                    name: name.clone(),
+                    table,
+                    filter: search_word,
+                };
+                match self.presets.save(preset) {
+                    Ok(()) => {
+                        self.status.info(format!("saved preset {}", name));
+                        self.view_mut().active_preset = Some(name);
+                    }
+                    Err(e) => self.status.error(e.to_string()),
+                }
+            }
+        }
+    }
+
+    /// Close the preset dropdown, or back out of naming a preset.
+    pub fn cancel_preset_menu(&mut self) {
+        if self.preset_menu.as_mut().is_some_and(PresetMenu::cancel) {
+            self.close_preset_menu();
+        }
+    }
+
+    fn close_preset_menu(&mut self) {
+        self.preset_menu = None;
+        self.input_mode = InputMode::Normal;
+        self.focus_area = FocusArea::Lower;
+    }
+
+    pub fn edit_preset_menu(&mut self, code: KeyCode) {
+        let Some(menu) = &mut self.preset_menu else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) if menu.is_naming() => menu.enter_char(c),
+            KeyCode::Backspace => menu.delete_char(),
+            KeyCode::Down | KeyCode::Tab if !menu.is_naming() => menu.next(),
+            KeyCode::Up if !menu.is_naming() => menu.previous(),
+            _ => {}
+        }
+    }
+
+    /// Switch the current view to `page` and fetch it.
+    fn show_page(&mut self, page: Page) {
+        let table = self.working_data.table();
+        self.set_page(table, page);
+        self.load(table, None, "");
+    }
+
+    /// Take an MM page fetched for `query`, unless the view has moved to another
+    /// page, search or sort since.
+    fn apply_mm_page(&mut self, query: Query, data: TaskPage<MmVolumeTask>) {
+        if query != *self.mm_view.query() {
+            return;
+        }
+        if data.items.is_empty() && data.page.offset > 0 {
+            // rows were deleted or filtered away under us
+            self.set_page(TaskTable::Mm, data.page.last(data.total));
+            return self.load(TaskTable::Mm, None, "");
+        }
+
+        let anchor = self.selected_id_of(TaskTable::Mm);
+        self.mm_items = data.items;
+        self.mm_view.total = data.total;
+        self.mm_view.newest = data.newest;
+        self.mm_view.shown = Some(query);
+        self.mm_view.refreshed_at = Instant::now();
+        self.longest_mm_item_lens = column_widths(&self.mm_items);
+        self.reselect(TaskTable::Mm, anchor);
+    }
+
+    /// Take a DEX page fetched for `query`, unless the view has moved to another
+    /// page, search or sort since.
+    fn apply_dex_page(&mut self, query: Query, data: TaskPage<DexVolumeTask>) {
+        if query != *self.dex_view.query() {
+            return;
+        }
+        if data.items.is_empty() && data.page.offset > 0 {
+            self.set_page(TaskTable::Dex, data.page.last(data.total));
+            return self.load(TaskTable::Dex, None, "");
+        }
+
+        let anchor = self.selected_id_of(TaskTable::Dex);
+        self.dex_items = data.items;
+        self.dex_view.total = data.total;
+        self.dex_view.newest = data.newest;
+        self.dex_view.shown = Some(query);
+        self.dex_view.refreshed_at = Instant::now();
+        self.longest_dex_item_lens = column_widths(&self.dex_items);
+        self.reselect(TaskTable::Dex, anchor);
+    }
+
+    /// `update_time` of the selected row, for an edit starting on it.
+    fn selected_version(&self) -> Option<NaiveDateTime> {
+        let row = self.state.selected()?;
+        match self.working_data {
+            WorkingData::Mm => self.quick_rows(&self.mm_items).get(row)?.version(),
+            WorkingData::Dex => self.quick_rows(&self.dex_items).get(row)?.version(),
+        }
+    }
+
+    /// Id of the row selected in the view of `table`, shown or parked.
+    fn selected_id_of(&self, table: TaskTable) -> Option<u64> {
+        let row = if table == self.working_data.table() {
+            self.state.selected()?
+        } else {
+            self.view_of(table).table_state.selected()?
+        };
+        match table {
+            TaskTable::Mm => self
+                .quick_rows_of(table, &self.mm_items)
+                .get(row)
+                .map(|data| data.id),
+            TaskTable::Dex => self
+                .quick_rows_of(table, &self.dex_items)
+                .get(row)
+                .map(|data| data.id),
+        }
+    }
+
+    /// Position of the row with `id` among the rows the view of `table` shows.
+    fn row_index(&self, table: TaskTable, id: u64) -> Option<usize> {
+        match table {
+            TaskTable::Mm => self
+                .quick_rows_of(table, &self.mm_items)
+                .iter()
+                .position(|data| data.id == id),
+            TaskTable::Dex => self
+                .quick_rows_of(table, &self.dex_items)
+                .iter()
+                .position(|data| data.id == id),
+        }
+    }
+
+    /// Keep the selection of `table` on the row with `id` after its rows were replaced,
+    /// so rows added or removed upstream do not move the cursor to another task.
+    fn reselect(&mut self, table: TaskTable, id: Option<u64>) {
+        let index = id.and_then(|id| self.row_index(table, id));
+        if table != self.working_data.table() {
+            if let Some(i) = index {
+                let view = self.view_of_mut(table);
+                view.table_state.select(Some(i));
+                view.scroll_state = view.scroll_state.position(i * ITEM_HEIGHT);
+            }
+            return;
+        }
+
+        match index {
+            Some(i) => {
+                self.state.select(Some(i));
+                self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
+            }
+            None => self.clamp_selection(),
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        let last = self.row_count().saturating_sub(1);
+        if let Some(i) = self.state.selected() {
+            if i > last {
+                self.state.select(Some(last));
+                self.scroll_state = self.scroll_state.position(last * ITEM_HEIGHT);
+            }
+        }
+    }
+
     pub fn next_column(&mut self) {
         self.state.select_next_column();
     }
@@ -771,6 +1354,10 @@ impl App {
     }
 
     pub fn edit_cell(&mut self) {
+        if self.selected_row_busy() {
+            return;
+        }
+
         let mut can_edit = true;
 
         if let Some(content) = self.get_current_cell_content() {
@@ -778,9 +1365,16 @@ impl App {
             self.input = content.cell_value.to_owned();
             self.editing_key = content.key_name;
             self.editing_key_value = content.key_value;
+            self.editing_version = self.selected_version();
             self.editing_column = content.cell_name.clone();
+            self.editing_original = content.cell_value.clone();
 
-            if content.cell_name.eq("id") || content.cell_name.eq("col") {
+            if !self
+                .working_data
+                .table()
+                .schema()
+                .is_editable(&content.cell_name)
+            {
                 can_edit = false;
             }
         }
@@ -798,79 +1392,14 @@ impl App {
             }
             1 => {
                 // info!("key : {}", self.header_item_max_index);
-                match self.working_data {
-                    WorkingData::Mm => {
-                        self.working_data = WorkingData::Dex;
-                        self.search_word = "".to_string();
-                        {
-                            let mut _key_word = SEARCH_KEY_WORD.lock().unwrap();
-                            *_key_word = "".to_string();
-                        }
-
-                        block_on(async {
-                            let config: Config = load_config();
-
-                            let db_url = format!(
-                                "mysql://{}:{}@{}:{}/{}",
-                                config.db_user,
-                                config.db_password,
-                                config.db_server,
-                                config.db_port,
-                                config.db_name
-                            );
-                            let db1 = Database::new(&db_url).await.unwrap();
-
-                            let dex_tasks: Vec<DexVolumeTask> = db1
-                                .get_all_dex_volume_task(&self.search_word)
-                                .await
-                                .unwrap();
-                            let mut _datas: Vec<DexVolumeTask> = vec![];
-                            // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                            for _temp in dex_tasks {
-                                _datas.push(_temp);
-                            }
-
-                            self.dex_items = _datas;
-                        });
-
-                        self.longest_dex_item_lens = constraint_dex_len_calculator(&self.dex_items);
-                        self.focus_area = FocusArea::Lower;
-                    }
-                    WorkingData::Dex => {
-                        self.working_data = WorkingData::Mm;
-
-                        self.search_word = "".to_string();
-
-                        block_on(async {
-                            let config: Config = load_config();
-
-                            let db_url = format!(
-                                "mysql://{}:{}@{}:{}/{}",
-                                config.db_user,
-                                config.db_password,
-                                config.db_server,
-                                config.db_port,
-                                config.db_name
-                            );
-                            let db1 = Database::new(&db_url).await.unwrap();
-
-                            let mm_tasks: Vec<MmVolumeTask> =
-                                db1.get_all_mm_volume_task(&self.search_word).await.unwrap();
-                            let mut _datas: Vec<MmVolumeTask> = vec![];
-                            // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                            for _temp in mm_tasks {
-                                _datas.push(_temp);
-                            }
-
-                            self.mm_items = _datas;
-                        });
-
-                        self.longest_mm_item_lens = constraint_mm_len_calculator(&self.mm_items);
-                        // self.scroll_state =
-                        //     ScrollbarState::new((&self.mm_items.len() - 1) * ITEM_HEIGHT);
-                        self.focus_area = FocusArea::Lower;
-                    }
-                }
+                self.switch_view();
+                self.focus_area = FocusArea::Lower;
+            }
+            2 => self.open_env_menu(),
+            3 => {
+                let table = self.working_data.table();
+                self.preset_menu = Some(PresetMenu::new(&self.presets, table));
+                self.input_mode = InputMode::Presets;
             }
             _ => {}
         }
@@ -896,218 +1425,451 @@ impl App {
     pub fn submit_message(&mut self) {
         // self.messages.push(self.input.clone());
 
-        let is_num = is_numeric(&self.input.clone());
+        // consult the column spec and row invariants of the current table
+        let schema = self.working_data.table().schema();
+        let validation = schema
+            .validate(&self.editing_column, &self.input)
+            .and_then(|column| {
+                let mut row = self.selected_row_values().unwrap_or_default();
+                for (name, value) in row.iter_mut() {
+                    if *name == column.name {
+                        *value = self.input.clone();
+                    }
+                }
+                let row: Vec<(&str, &str)> = row
+                    .iter()
+                    .map(|(name, value)| (*name, value.as_str()))
+                    .collect();
+                Ok(schema.check_row(&row, &[column.name])?)
+            });
+        let data_valid = validation.is_ok();
 
-        // info!("{} is_num {}", &self.input.clone(), is_num);
+        if self.focus_area == FocusArea::Upper && self.header_item_selected_index == 0 {
+            // submit search word, keeping the box open while it does not parse
+            let table = self.working_data.table();
+            match Filter::parse(table.schema(), &self.view().search_word) {
+                Ok(filter) => {
+                    self.search_error = None;
+                    let view = self.view_mut();
+                    view.active_preset = None;
+                    view.set_filter(filter);
+                    self.show_page(Page::first(self.page_size));
+
+                    self.focus_area = FocusArea::Lower;
+                    self.input_mode = InputMode::Normal;
+                }
+                Err(e) => self.search_error = Some(e.to_string()),
+            }
+        } else {
+            if data_valid {
+                self.reset_cursor();
+                self.input_mode = InputMode::Normal;
+                self.show_popup = false;
+                self.input_error = None;
+
+                info!(" update record : {} ", self.editing_key_value);
+                let table = self.working_data.table();
+                let id = self.editing_key_value.parse().ok();
+                let retry = Retry::Cell {
+                    key_value: self.editing_key_value.clone(),
+                    version: self.editing_version,
+                    column: self.editing_column.clone(),
+                    original: self.editing_original.clone(),
+                    input: self.input.clone(),
+                };
 
-        let mut data_valid = true;
+                let db = Arc::clone(&self.db);
+                let key = self.editing_key.clone();
+                let key_value = self.editing_key_value.clone();
+                let column = self.editing_column.clone();
+                let value = self.input.clone();
+                let original = self.editing_original.clone();
+                let version = self.editing_version;
+                self.actions.spawn(table, id, "保存中…", retry, async move {
+                    let expected = Expected {
+                        version,
+                        originals: &[(column.as_str(), original.as_str())],
+                    };
+                    db.update_record(table, &key, &key_value, &column, &value, expected)
+                        .await?;
+                    Ok(Outcome::Saved(id.unwrap_or_default()))
+                });
 
-        // currently use manual check
-        if self.editing_column.clone().eq("launch_id") && !is_num {
-            data_valid = false;
+                self.input.clear();
+            } else if let Err(e) = validation {
+                self.input_error = Some(e.to_string());
+            }
         }
+    }
 
-        if self.editing_column.clone().eq("target_volume") && !is_num {
-            data_valid = false;
+    pub fn run_action(&mut self, action: RowAction) {
+        match action {
+            RowAction::New => self.open_insert_form(),
+            _ if self.selected_row_busy() => {}
+            RowAction::Edit => self.open_edit_form(),
+            RowAction::Copy | RowAction::Delete => self.request_action(action),
         }
+    }
 
-        if self.editing_column.clone().eq("do_status") && !is_num {
-            data_valid = false;
-        }
+    /// Ask for confirmation before running `action` on the selected row.
+    fn request_action(&mut self, action: RowAction) {
+        let Some(row) = self.selected_row_values() else {
+            return;
+        };
+        let Some((_, key_value)) = row.into_iter().find(|(name, _)| *name == "id") else {
+            return;
+        };
 
-        if self.editing_column.clone().eq("use_wallet_type") && !is_num {
-            data_valid = false;
-        }
+        self.pending_action = Some((action, key_value));
+        self.input_error = None;
+        self.input_mode = InputMode::Confirm;
+    }
 
-        if self.editing_column.clone().eq("buy_rate") && !is_num {
-            data_valid = false;
+    pub fn confirm_action(&mut self) {
+        let Some((action, key_value)) = self.pending_action.take() else {
+            return;
+        };
+        let table = self.working_data.table();
+        let id = key_value.parse().ok();
+
+        // the confirmation was given for this id; refuse if the cursor is no longer on it
+        if id.is_none() || self.selected_id_of(table) != id {
+            self.input_error = Some(format!(
+                "任务 #{} 已不在所选行, 请按ESC后重新选择",
+                key_value
+            ));
+            self.pending_action = Some((action, key_value));
+            return;
         }
+        self.cancel_action();
 
-        if self.editing_column.clone().eq("buy_per_low") && !is_num {
-            data_valid = false;
-        }
+        info!(" {:?} record : {} ", action, key_value);
+        let label = match action {
+            RowAction::Delete => "删除中…",
+            _ => "复制中…",
+        };
+        let retry = Retry::Confirm(action, key_value.clone());
+
+        let db = Arc::clone(&self.db);
+        self.actions.spawn(table, id, label, retry, async move {
+            match action {
+                RowAction::Delete => {
+                    if !db.delete_record(table, &key_value).await? {
+                        return Err(DbError::NotFound {
+                            table: table.schema().table,
+                            key: key_value,
+                        });
+                    }
+                    Ok(Outcome::Deleted)
+                }
+                _ => Ok(Outcome::Created(db.copy_record(table, &key_value).await?)),
+            }
+        });
+    }
 
-        if self.editing_column.clone().eq("buy_per_high") && !is_num {
-            data_valid = false;
-        }
+    pub fn cancel_action(&mut self) {
+        self.pending_action = None;
+        self.input_error = None;
+        self.input_mode = InputMode::Normal;
+    }
 
-        if self.editing_column.clone().eq("sell_percent") && !is_num {
-            data_valid = false;
-        }
+    pub fn open_insert_form(&mut self) {
+        self.form = Some(Form::insert(self.working_data.table()));
+        self.input_mode = InputMode::Form;
+    }
 
-        if self.editing_column.clone().eq("frequent_low") && !is_num {
-            data_valid = false;
-        }
+    pub fn open_edit_form(&mut self) {
+        let Some(row) = self.selected_row_values() else {
+            return;
+        };
+        let Some(key_value) = row
+            .iter()
+            .find(|(name, _)| *name == "id")
+            .map(|(_, value)| value.clone())
+        else {
+            return;
+        };
 
-        if self.editing_column.clone().eq("frequent_high") && !is_num {
-            data_valid = false;
-        }
+        let version = self.selected_version();
+        self.form = Some(Form::edit(
+            self.working_data.table(),
+            key_value,
+            version,
+            row,
+        ));
+        self.input_mode = InputMode::Form;
+    }
+
+    pub fn edit_form(&mut self, code: KeyCode) {
+        let Some(form) = self.form.as_mut() else {
+            return;
+        };
 
-        if self.editing_column.clone().eq("real_sol") && !is_num {
-            data_valid = false;
+        match code {
+            KeyCode::Tab | KeyCode::Down => form.next_field(),
+            KeyCode::BackTab | KeyCode::Up => form.previous_field(),
+            KeyCode::Char(to_insert) => form.enter_char(to_insert),
+            KeyCode::Backspace => form.delete_char(),
+            KeyCode::Left => form.move_cursor_left(),
+            KeyCode::Right => form.move_cursor_right(),
+            _ => {}
         }
+    }
+
+    pub fn submit_form(&mut self) {
+        let Some(form) = self.form.as_mut() else {
+            return;
+        };
 
-        if self.editing_column.clone().eq("copy") {
-            data_valid = false;
+        if !form.validate() {
+            return;
         }
 
-        if self.editing_column.clone().eq("del") {
-            data_valid = false;
+        let table = form.table;
+        let values: Vec<(String, String)> = form
+            .values()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let originals: Vec<(String, String)> = form
+            .originals()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let (key_value, version) = match &form.mode {
+            FormMode::Insert => (None, None),
+            FormMode::Edit { key_value, version } => (Some(key_value.clone()), *version),
+        };
+        if key_value.is_some() && values.is_empty() {
+            return self.cancel_form();
         }
 
-        if self.focus_area == FocusArea::Upper && self.header_item_selected_index == 0 {
-            // submit search word
+        let id = key_value
+            .as_deref()
+            .and_then(|key_value| key_value.parse().ok());
+        let retry = self.form.take().map_or(Retry::None, Retry::Form);
+        self.input_mode = InputMode::Normal;
 
-            {
-                let mut _key_word = SEARCH_KEY_WORD.lock().unwrap();
-                *_key_word = self.search_word.clone();
+        let db = Arc::clone(&self.db);
+        self.actions.spawn(table, id, "保存中…", retry, async move {
+            let values: Vec<(&str, &str)> = values
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect();
+            match key_value {
+                Some(key_value) => {
+                    let originals: Vec<(&str, &str)> = originals
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), value.as_str()))
+                        .collect();
+                    let expected = Expected {
+                        version,
+                        originals: &originals,
+                    };
+                    db.update_columns(table, "id", &key_value, &values, expected)
+                        .await?;
+                    Ok(Outcome::Saved(id.unwrap_or_default()))
+                }
+                None => Ok(Outcome::Created(match table {
+                    TaskTable::Mm => db.insert_mm_volume_task(&values).await?,
+                    TaskTable::Dex => db.insert_dex_volume_task(&values).await?,
+                })),
             }
+        });
+    }
 
-            block_on(async {
-                let config: Config = load_config();
-
-                let db_url = format!(
-                    "mysql://{}:{}@{}:{}/{}",
-                    config.db_user,
-                    config.db_password,
-                    config.db_server,
-                    config.db_port,
-                    config.db_name
-                );
-                let db1 = Database::new(&db_url).await.unwrap();
-
-                if self.working_data == WorkingData::Mm {
-                    let mm_tasks: Vec<MmVolumeTask> =
-                        db1.get_all_mm_volume_task(&self.search_word).await.unwrap();
-                    let mut _datas: Vec<MmVolumeTask> = vec![];
-                    // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                    for _temp in mm_tasks {
-                        _datas.push(_temp);
-                    }
+    pub fn cancel_form(&mut self) {
+        self.form = None;
+        self.input_mode = InputMode::Normal;
+    }
 
-                    self.mm_items = _datas;
-                } else {
-                    let dex_tasks: Vec<DexVolumeTask> = db1
-                        .get_all_dex_volume_task(&self.search_word)
-                        .await
-                        .unwrap();
-                    let mut _datas: Vec<DexVolumeTask> = vec![];
-                    // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                    for _temp in dex_tasks {
-                        _datas.push(_temp);
+    /// Fetch the page of `table` in the background, then select `row` if given.
+    fn load(&mut self, table: TaskTable, row: Option<u64>, label: &'static str) {
+        // follow the table height, which may have changed since the page was set
+        let page = self.view_of(table).query().page.resized(self.page_size);
+        self.set_page(table, page);
+        let query = self.view_of(table).query().clone();
+
+        let db = Arc::clone(&self.db);
+        self.actions
+            .spawn(table, row, label, Retry::None, async move {
+                let (filter, sort) = (&query.filter, query.sort);
+                Ok(match table {
+                    TaskTable::Mm => {
+                        let data = db.get_mm_volume_task_page(filter, sort, page, None).await?;
+                        Outcome::MmLoaded(query, data)
                     }
-
-                    self.dex_items = _datas;
-                }
+                    TaskTable::Dex => {
+                        let data = db
+                            .get_dex_volume_task_page(filter, sort, page, None)
+                            .await?;
+                        Outcome::DexLoaded(query, data)
+                    }
+                })
             });
+    }
 
-            self.focus_area = FocusArea::Lower;
-            self.input_mode = InputMode::Normal;
-        } else {
-            if data_valid {
-                self.reset_cursor();
-                self.input_mode = InputMode::Normal;
-                self.show_popup = false;
+    /// Find the page of `table` holding row `id`, keeping the row marked until it is shown.
+    fn locate(&mut self, table: TaskTable, id: u64, label: &'static str) {
+        let query = self.view_of(table).query();
+        let (sort, filter) = (query.sort, query.filter.clone());
 
-                // ------- ugly code start
-                block_on(async {
-                    let config: Config = load_config();
-
-                    let db_url = format!(
-                        "mysql://{}:{}@{}:{}/{}",
-                        config.db_user,
-                        config.db_password,
-                        config.db_server,
-                        config.db_port,
-                        config.db_name
-                    );
-                    let db1 = Database::new(&db_url).await.unwrap();
-                    info!(" update record : {} ", self.editing_key_value);
-                    db1.update_record(
-                        &self.editing_key,
-                        &self.editing_key_value,
-                        &self.editing_column,
-                        &self.input.clone(),
-                    )
-                    .await
-                    .unwrap();
-
-                    if self.working_data == WorkingData::Mm {
-                        let mm_tasks: Vec<MmVolumeTask> =
-                            db1.get_all_mm_volume_task(&self.search_word).await.unwrap();
-                        let mut _datas: Vec<MmVolumeTask> = vec![];
-                        // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                        for _temp in mm_tasks {
-                            _datas.push(_temp);
-                        }
+        let db = Arc::clone(&self.db);
+        self.actions
+            .spawn(table, Some(id), label, Retry::None, async move {
+                let offset = db.row_offset(table, &filter, sort, id).await?;
+                Ok(Outcome::Located { id, offset })
+            });
+    }
 
-                        self.mm_items = _datas;
-                    } else {
-                        let dex_tasks: Vec<DexVolumeTask> = db1
-                            .get_all_dex_volume_task(&self.search_word)
-                            .await
-                            .unwrap();
-                        let mut _datas: Vec<DexVolumeTask> = vec![];
-                        // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                        for _temp in dex_tasks {
-                            _datas.push(_temp);
-                        }
+    /// Apply the result of a background action.
+    pub fn finish_action(&mut self, finished: Finished) {
+        let Some(pending) = self.actions.finish(finished.ticket) else {
+            return;
+        };
 
-                        self.dex_items = _datas;
+        match finished.outcome {
+            Ok(Outcome::Saved(id)) => {
+                info!(" saved record : {} ", id);
+                self.status.info(format!("saved #{}", id));
+                // keep the row marked until the fresh data is in
+                self.load(pending.table, Some(id), pending.label);
+            }
+            Ok(Outcome::Created(id)) => {
+                info!(" created record : {} ", id);
+                self.status.info(format!("saved #{}", id));
+                // the new row is rarely on the page shown, e.g. the last one in id order
+                return self.locate(pending.table, id, pending.label);
+            }
+            Ok(Outcome::Located { id, offset }) => {
+                match offset {
+                    Some(offset) => {
+                        self.set_page(pending.table, Page::holding(offset, self.page_size))
                     }
+                    None => self
+                        .status
+                        .info(format!("#{} does not match the search", id)),
+                }
+                return self.load(pending.table, Some(id), pending.label);
+            }
+            Ok(Outcome::Deleted) => {
+                if let Some(id) = pending.row {
+                    self.status.info(format!("deleted #{}", id));
+                }
+                self.load(pending.table, pending.row, pending.label);
+            }
+            Ok(Outcome::MmLoaded(query, data)) => self.apply_mm_page(query, data),
+            Ok(Outcome::DexLoaded(query, data)) => self.apply_dex_page(query, data),
+            Ok(Outcome::Connected(connection)) => return self.switch_environment(connection),
+            Err(DbError::Conflict {
+                key,
+                version,
+                columns,
+                ..
+            }) if self.input_mode == InputMode::Normal
+                && pending.table == self.working_data.table() =>
+            {
+                info!(" edit conflict : {} ", key);
+                self.conflict = Some(Conflict {
+                    table: pending.table,
+                    key_value: key,
+                    version,
+                    columns,
                 });
+                self.input_mode = InputMode::Conflict;
+                // show the row as it is now behind the dialog
+                return self.load(pending.table, None, "");
+            }
+            Err(e) => return self.retry(pending.retry, pending.table, e.to_string()),
+        }
 
-                self.input.clear();
-            } else if self.editing_column.clone().eq("copy")
-                || self.editing_column.clone().eq("del")
-            {
-                self.reset_cursor();
-                self.input_mode = InputMode::Normal;
-                self.show_popup = false;
+        if let Some(id) = pending.row {
+            if pending.table == self.working_data.table() {
+                self.select_id(id);
+            }
+        }
+    }
 
-                // ------- ugly code start
-                block_on(async {
-                    let config: Config = load_config();
-
-                    let db_url = format!(
-                        "mysql://{}:{}@{}:{}/{}",
-                        config.db_user,
-                        config.db_password,
-                        config.db_server,
-                        config.db_port,
-                        config.db_name
-                    );
-                    let db1 = Database::new(&db_url).await.unwrap();
-                    // info!(" self.editing_key : {} ", self.editing_key);
-
-                    // Operate copy work
-                    if self.editing_column.clone().eq("copy") {
-                        info!(" copy record : {:?} ", self.editing_key_value);
-                        db1.copy_dex_record_by_id(&self.editing_key_value)
-                            .await
-                            .unwrap();
-                    } else if self.editing_column.clone().eq("del") {
-                        info!(" delete record : {:?} ", self.editing_key_value);
-                        db1.delete_dex_record_by_id(&self.editing_key_value)
-                            .await
-                            .unwrap();
-                    }
+    /// Write our values of a conflicting edit over the ones the row has now.
+    pub fn overwrite_conflict(&mut self) {
+        let Some(conflict) = self.conflict.take() else {
+            return;
+        };
+        self.input_mode = InputMode::Normal;
 
-                    let dex_tasks = db1
-                        .get_all_dex_volume_task(&self.search_word)
-                        .await
-                        .unwrap();
-                    let mut _datas: Vec<DexVolumeTask> = vec![];
-                    // let mut array = GLOBAL_ARRAY.lock().unwrap();
-                    for _temp in dex_tasks {
-                        _datas.push(_temp);
-                    }
+        info!(" overwrite record : {} ", conflict.key_value);
+        let table = conflict.table;
+        let id = conflict.key_value.parse().ok();
+        let db = Arc::clone(&self.db);
+        self.actions
+            .spawn(table, id, "保存中…", Retry::None, async move {
+                // expect the row as the dialog showed it, so a further change is caught too
+                db.update_columns(
+                    table,
+                    "id",
+                    &conflict.key_value,
+                    &conflict.values(),
+                    Expected {
+                        version: conflict.version,
+                        originals: &conflict.originals(),
+                    },
+                )
+                .await?;
+                Ok(Outcome::Saved(id.unwrap_or_default()))
+            });
+    }
 
-                    self.dex_items = _datas;
-                });
+    /// Drop a conflicting edit, keeping the values the row has now.
+    pub fn discard_conflict(&mut self) {
+        self.conflict = None;
+        self.input_mode = InputMode::Normal;
+    }
 
-                self.input.clear();
+    /// Reopen the popup of a failed action with its error, unless the user is busy elsewhere.
+    fn retry(&mut self, retry: Retry, table: TaskTable, error: String) {
+        info!(" action failed : {} ", error);
+        if self.input_mode != InputMode::Normal || table != self.working_data.table() {
+            return self.status.error(error);
+        }
+
+        match retry {
+            Retry::None => self.status.error(error),
+            Retry::Cell {
+                key_value,
+                version,
+                column,
+                original,
+                input,
+            } => {
+                self.editing_key = "id".to_string();
+                self.editing_key_value = key_value;
+                self.editing_version = version;
+                self.editing_column = column;
+                self.editing_original = original;
+                self.character_index = input.chars().count();
+                self.input = input;
+                self.input_error = Some(error);
+                self.show_popup = true;
+                self.input_mode = InputMode::Editing;
+            }
+            Retry::Form(mut form) => {
+                form.error = Some(error);
+                self.form = Some(form);
+                self.input_mode = InputMode::Form;
             }
+            Retry::Confirm(action, key_value) => {
+                self.pending_action = Some((action, key_value));
+                self.input_error = Some(error);
+                self.input_mode = InputMode::Confirm;
+            }
+        }
+    }
+
+    /// Move the table selection to the row with `id`, if it is loaded.
+    fn select_id(&mut self, id: u64) {
+        if let Some(i) = self.row_index(self.working_data.table(), id) {
+            self.state.select(Some(i));
+            self.scroll_state = self.scroll_state.position(i * ITEM_HEIGHT);
         }
     }
 
@@ -1117,6 +1879,8 @@ impl App {
         self.input_mode = InputMode::Normal;
         self.show_popup = false;
         self.input.clear();
+        self.input_error = None;
+        self.search_error = None;
     }
 
     fn reset_cursor(&mut self) {
@@ -1126,11 +1890,12 @@ impl App {
     fn byte_index(&self) -> usize {
         match self.focus_area {
             FocusArea::Upper => self
+                .view()
                 .search_word
                 .char_indices()
                 .map(|(i, _)| i)
                 .nth(self.search_word_character_index)
-                .unwrap_or(self.search_word.len()),
+                .unwrap_or(self.view().search_word.len()),
             FocusArea::Lower => self
                 .input
                 .char_indices()
@@ -1141,6 +1906,19 @@ impl App {
     }
 
     fn delete_char(&mut self) {
+        if self.focus_area == FocusArea::Upper {
+            let current_index = self.search_word_character_index;
+            if current_index != 0 {
+                let search_word = &mut self.view_mut().search_word;
+                let before_char_to_delete = search_word.chars().take(current_index - 1);
+                let after_char_to_delete = search_word.chars().skip(current_index);
+                *search_word = before_char_to_delete.chain(after_char_to_delete).collect();
+                self.move_cursor_left();
+                self.search_error = None;
+            }
+            return;
+        }
+
         let is_not_cursor_leftmost = self.character_index != 0;
         if is_not_cursor_leftmost {
             // Method "remove" is not used on the saved text for deleting the selected char.
@@ -1159,6 +1937,7 @@ impl App {
             // By leaving the selected one out, it is forgotten and therefore deleted.
             self.input = before_char_to_delete.chain(after_char_to_delete).collect();
             self.move_cursor_left();
+            self.input_error = None;
         }
     }
 
@@ -1166,7 +1945,7 @@ impl App {
         // new_cursor_pos.clamp(0, self.input.chars().count())
 
         match self.focus_area {
-            FocusArea::Upper => new_cursor_pos.clamp(0, self.search_word.chars().count()),
+            FocusArea::Upper => new_cursor_pos.clamp(0, self.view().search_word.chars().count()),
             FocusArea::Lower => new_cursor_pos.clamp(0, self.input.chars().count()),
         }
     }
@@ -1206,12 +1985,15 @@ impl App {
         info!("new_char : {}", new_char);
         match self.focus_area {
             FocusArea::Upper => {
-                self.search_word.insert(self.byte_index(), new_char);
+                let index = self.byte_index();
+                self.view_mut().search_word.insert(index, new_char);
                 self.move_cursor_right();
+                self.search_error = None;
             }
             FocusArea::Lower => {
                 self.input.insert(self.byte_index(), new_char);
                 self.move_cursor_right();
+                self.input_error = None;
             }
         }
         // let index = self.byte_index();
@@ -1224,250 +2006,33 @@ impl App {
         let selected_column = self.state.selected_column().unwrap_or(0);
 
         match self.working_data {
-            WorkingData::Mm => {
-                let data = self.mm_items.get(selected_row)?;
-
-                match selected_column {
-                    0 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "id".to_string(),
-                        cell_value: data.id().to_string(),
-                    }),
-                    1 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "launch_id".to_string(),
-                        cell_value: data.launch_id().to_string(),
-                    }),
-                    2 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "token_add".to_string(),
-                        cell_value: data.token_add().to_string(),
-                    }),
-                    3 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "target_volume".to_string(),
-                        cell_value: data.target_volume().to_string(),
-                    }),
-                    4 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "do_status".to_string(),
-                        cell_value: data.do_status().to_string(),
-                    }),
-                    5 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "use_wallet_type".to_string(),
-                        cell_value: data.use_wallet_type().to_string(),
-                    }),
-                    6 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "remark".to_string(),
-                        cell_value: data.remark().to_string(),
-                    }),
-                    7 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_rate".to_string(),
-                        cell_value: data.buy_rate().to_string(),
-                    }),
-                    8 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_per_low".to_string(),
-                        cell_value: data.buy_per_low().to_string(),
-                    }),
-                    9 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_per_high".to_string(),
-                        cell_value: data.buy_per_high().to_string(),
-                    }),
-                    10 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "sell_percent".to_string(),
-                        cell_value: data.sell_percent().to_string(),
-                    }),
-                    11 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "frequent_low".to_string(),
-                        cell_value: data.frequent_low().to_string(),
-                    }),
-                    12 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "frequent_high".to_string(),
-                        cell_value: data.frequent_high().to_string(),
-                    }),
-                    13 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "real_sol".to_string(),
-                        cell_value: data.real_sol().to_string(),
-                    }),
-                    14 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "create_time".to_string(),
-                        cell_value: data.create_time.to_string(),
-                    }),
-                    15 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "update_time".to_string(),
-                        cell_value: data.update_time.to_string(),
-                    }),
-                    16 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "col1".to_string(),
-                        cell_value: "".to_string(),
-                    }),
-                    17 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "col2".to_string(),
-                        cell_value: "".to_string(),
-                    }),
-                    18 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "col3".to_string(),
-                        cell_value: "".to_string(),
-                    }),
-
-                    _ => None,
-                }
-            }
-            WorkingData::Dex => {
-                let data = self.dex_items.get(selected_row)?;
-
-                match selected_column {
-                    0 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "id".to_string(),
-                        cell_value: data.id().to_string(),
-                    }),
-                    1 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "pool_id".to_string(),
-                        cell_value: data.pool_id().to_string(),
-                    }),
-                    2 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "token_add".to_string(),
-                        cell_value: data.token_add().to_string(),
-                    }),
-                    3 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "mm_type".to_string(),
-                        cell_value: data.mm_type().to_string(),
-                    }),
-                    4 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "remark".to_string(),
-                        cell_value: data.remark().to_string(),
-                    }),
-                    5 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "target_price".to_string(),
-                        cell_value: data.target_price().to_string(),
-                    }),
-                    6 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "stop_price_per".to_string(),
-                        cell_value: data.stop_price_per().to_string(),
-                    }),
-                    7 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "do_status".to_string(),
-                        cell_value: data.do_status().to_string(),
-                    }),
-                    8 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_rate".to_string(),
-                        cell_value: data.buy_rate().to_string(),
-                    }),
-                    9 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_per_low".to_string(),
-                        cell_value: data.buy_per_low().to_string(),
-                    }),
-                    10 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "buy_per_high".to_string(),
-                        cell_value: data.buy_per_high().to_string(),
-                    }),
-                    11 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "sell_percent".to_string(),
-                        cell_value: data.sell_percent().to_string(),
-                    }),
-                    12 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "frequent_low".to_string(),
-                        cell_value: data.frequent_low().to_string(),
-                    }),
-                    13 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "frequent_high".to_string(),
-                        cell_value: data.frequent_high().to_string(),
-                    }),
-                    14 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "bsdiff".to_string(),
-                        cell_value: data.bsdiff().to_string(),
-                    }),
-                    15 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "create_time".to_string(),
-                        cell_value: data.create_time().to_string(),
-                    }),
-                    16 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "update_time".to_string(),
-                        cell_value: data.update_time().to_string(),
-                    }),
-                    17 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "copy".to_string(),
-                        cell_value: data.copy().to_string(),
-                    }),
-                    18 => Some(SelectedCell {
-                        key_name: "id".to_string(),
-                        key_value: data.id().to_string(),
-                        cell_name: "del".to_string(),
-                        cell_value: data.del().to_string(),
-                    }),
-
-                    _ => None,
-                }
-            }
+            WorkingData::Mm => selected_cell(
+                &self.quick_rows(&self.mm_items),
+                selected_row,
+                selected_column,
+            ),
+            WorkingData::Dex => selected_cell(
+                &self.quick_rows(&self.dex_items),
+                selected_row,
+                selected_column,
+            ),
+        }
+    }
+
+    /// Whether an action is still running on the selected row.
+    fn selected_row_busy(&self) -> bool {
+        let table = self.working_data.table();
+        self.selected_id_of(table)
+            .is_some_and(|id| self.actions.row_label(table, id).is_some())
+    }
+
+    /// Column names and raw values of the selected row.
+    fn selected_row_values(&self) -> Option<Vec<(&'static str, String)>> {
+        let selected_row = self.state.selected()?;
+
+        match self.working_data {
+            WorkingData::Mm => row_values(&self.quick_rows(&self.mm_items), selected_row),
+            WorkingData::Dex => row_values(&self.quick_rows(&self.dex_items), selected_row),
         }
     }
 
@@ -1475,11 +2040,13 @@ impl App {
         let vertical = &Layout::vertical([
             Constraint::Length(3),
             Constraint::Min(8),
-            Constraint::Length(4),
+            Constraint::Length(1),
+            Constraint::Length(5),
         ]);
         let rects: std::rc::Rc<[Rect]> = vertical.split(frame.area());
 
         let header_horizontal = &Layout::horizontal([
+            Constraint::Length(48),
             Constraint::Length(20),
             Constraint::Length(20),
             Constraint::Length(20),
@@ -1493,13 +2060,34 @@ impl App {
 
         self.render_search_input(frame, header_rects[0]);
         self.render_header_button1(frame, header_rects[1]);
+        self.render_env_button(frame, header_rects[2]);
+        self.render_preset_button(frame, header_rects[3]);
         self.render_table(frame, rects[1]);
         self.render_scrollbar(frame, rects[1]);
-        self.render_footer(frame, rects[2]);
+        self.status.render_bar(frame, rects[2]);
+        self.render_footer(frame, rects[3]);
+        self.status.render_toasts(frame, rects[1]);
+        if let Some(menu) = &self.preset_menu {
+            menu.render(frame, header_rects[3]);
+        }
+        if let Some(menu) = &self.env_menu {
+            menu.render(frame, header_rects[2]);
+        }
 
-        let input = Paragraph::new(self.input.as_str())
+        let mut input_text = Text::from(self.input.as_str());
+        if let Some(error) = &self.input_error {
+            input_text.push_line(Line::from(error.as_str()).red());
+        }
+
+        let input = Paragraph::new(input_text)
             .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
+                InputMode::Normal
+                | InputMode::Form
+                | InputMode::Confirm
+                | InputMode::Presets
+                | InputMode::QuickFilter
+                | InputMode::Environments
+                | InputMode::Conflict => Style::default(),
                 InputMode::Editing => Style::default().fg(Color::Yellow),
             })
             .block(Block::bordered().title("Input"));
@@ -1514,27 +2102,16 @@ impl App {
 
             // frame.render_widget(block, area);
 
-            if self.focus_area == FocusArea::Lower && self.editing_column == "copy" {
-                let text = "是否复制本行, 确定请按回车, 取消按ESC";
-
-                let paragraph = Paragraph::new(text.white())
-                    .style(Style::default().fg(Color::White))
-                    .block(Block::default().borders(Borders::ALL).title("提示"));
-                frame.render_widget(paragraph, area);
-            } else if self.focus_area == FocusArea::Lower && self.editing_column == "del" {
-                let text = "是否删除本行, 确定请按回车, 取消按ESC";
-
-                let paragraph = Paragraph::new(text.white())
-                    .style(Style::default().fg(Color::White))
-                    .block(Block::default().borders(Borders::ALL).title("提示"));
-                frame.render_widget(paragraph, area);
-            } else {
-                frame.render_widget(input, area);
-            }
+            frame.render_widget(input, area);
 
             match self.input_mode {
                 // Hide the cursor. `Frame` does this by default, so we don't need to do anything here
-                InputMode::Normal => {}
+                InputMode::Normal
+                | InputMode::Confirm
+                | InputMode::Presets
+                | InputMode::QuickFilter
+                | InputMode::Environments
+                | InputMode::Conflict => {}
 
                 // Make the cursor visible and ask ratatui to put it at the specified coordinates after
                 // rendering
@@ -1546,7 +2123,32 @@ impl App {
                     // Move one line down, from the border to the input line
                     area.y + 1,
                 )),
+                InputMode::Form => {}
+            }
+        }
+
+        if let Some((action, key_value)) = &self.pending_action {
+            let area = popup_area(frame.area(), 40, 20);
+            frame.render_widget(Clear, area); //this clears out the background
+
+            let mut text = Text::from(format!("任务 #{}", key_value).bold().white());
+            text.push_line(action.prompt().unwrap_or_default().white());
+            if let Some(error) = &self.input_error {
+                text.push_line(Line::from(error.as_str()).red());
             }
+            let paragraph = Paragraph::new(text)
+                .style(Style::default().fg(Color::White))
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL).title("提示"));
+            frame.render_widget(paragraph, area);
+        }
+
+        if let Some(form) = &self.form {
+            form.render(frame);
+        }
+
+        if let Some(conflict) = &self.conflict {
+            conflict.render(frame);
         }
     }
 
@@ -1562,145 +2164,91 @@ impl App {
             .add_modifier(Modifier::REVERSED)
             .fg(self.colors.selected_cell_style_fg);
 
-        let header = match self.working_data {
-            WorkingData::Mm => vec![
-                "id",
-                "launch_id",
-                "token_add",
-                "target_volume",
-                "do_status",
-                "use_wallet_type",
-                "remark",
-                "buy_rate",
-                "buy_per_low",
-                "buy_per_high",
-                "sell_percent",
-                "frequent_low",
-                "frequent_high",
-                "real_sol",
-                "create_time",
-                "update_time",
-                "",
-                "",
-                "",
-            ],
-            WorkingData::Dex => vec![
-                "id",
-                "pool_id",
-                "token_add",
-                "mm_type",
-                "remark",
-                "target_price",
-                "stop_price_per",
-                "do_status",
-                "buy_rate",
-                "buy_per_low",
-                "buy_per_high",
-                "sell_percent",
-                "frequent_low",
-                "frequent_high",
-                "bsdiff",
-                "create_time",
-                "update_time",
-                "copy",
-                "del",
-            ],
-        }
-        .into_iter()
-        .map(Cell::from)
-        .collect::<Row>()
-        .style(header_style)
-        .height(1);
-
-        let rows: Vec<Row> = match self.working_data {
-            WorkingData::Mm => self
-                .mm_items
-                .iter()
-                .enumerate()
-                .map(|(i, data)| {
-                    let color = match i % 2 {
-                        0 => self.colors.normal_row_color,
-                        _ => self.colors.alt_row_color,
-                    };
-                    let item = data.ref_array();
-                    item.into_iter()
-                        .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                        .collect::<Row>()
-                        .style(Style::new().fg(self.colors.row_fg).bg(color))
-                        .height(3)
-                })
-                .collect(),
-            WorkingData::Dex => self
-                .dex_items
-                .iter()
-                .enumerate()
-                .map(|(i, data)| {
-                    let color = match i % 2 {
-                        0 => self.colors.normal_row_color,
-                        _ => self.colors.alt_row_color,
-                    };
-                    let item = data.ref_array();
-                    item.into_iter()
-                        .map(|content| Cell::from(Text::from(format!("\n{content}\n"))))
-                        .collect::<Row>()
-                        .style(Style::new().fg(self.colors.row_fg).bg(color))
-                        .height(3)
-                })
-                .collect(),
+        let sort = self.view().query().sort;
+        let columns = match self.working_data {
+            WorkingData::Mm => MmVolumeTask::COLUMNS,
+            WorkingData::Dex => DexVolumeTask::COLUMNS,
+        };
+        let header = columns
+            .iter()
+            .map(|name| match sort {
+                Some(sort) if sort.column == *name => {
+                    format!("{} {}", name, if sort.descending { "▼" } else { "▲" })
+                }
+                _ => name.to_string(),
+            })
+            .map(Cell::from)
+            .collect::<Row>()
+            .style(header_style)
+            .height(1);
+
+        // one page is as many rows as the table can show, below its header;
+        // fetched by `reload_if_resized` once the size settles
+        let fit = u64::from((area.height.saturating_sub(1) / ROW_HEIGHT).max(1));
+        if fit != self.page_size {
+            self.page_size = fit;
+            self.resized_at = Some(Instant::now());
+        }
+
+        let rows = match self.working_data {
+            WorkingData::Mm => self.task_rows(&self.quick_rows(&self.mm_items)),
+            WorkingData::Dex => self.task_rows(&self.quick_rows(&self.dex_items)),
+        };
+
+        // room for the spinner and label of a row being saved
+        let id_width = if self.actions.has_rows(self.working_data.table()) {
+            self.longest_mm_item_lens[0].max(ROW_STATUS_WIDTH)
+        } else {
+            self.longest_mm_item_lens[0]
         };
 
         let bar = " █ ";
         let width_mm = [
             // + 1 is for padding.
-            Constraint::Length(self.longest_mm_item_lens.0 + 1),
-            Constraint::Max(self.longest_mm_item_lens.1 + 6),
-            Constraint::Max(self.longest_mm_item_lens.2 + 5),
-            Constraint::Max(self.longest_mm_item_lens.0 + 9),
-            Constraint::Max(self.longest_mm_item_lens.0 + 5),
-            Constraint::Max(self.longest_mm_item_lens.0 + 4),
-            Constraint::Max(self.longest_mm_item_lens.0 + 4),
-            Constraint::Max(self.longest_mm_item_lens.0 + 4),
-            Constraint::Max(self.longest_mm_item_lens.0 + 7),
-            Constraint::Max(self.longest_mm_item_lens.0 + 8),
-            Constraint::Max(self.longest_mm_item_lens.0 + 9),
-            Constraint::Max(self.longest_mm_item_lens.0 + 8),
-            Constraint::Max(self.longest_mm_item_lens.0 + 9),
-            Constraint::Max(self.longest_mm_item_lens.0 + 9),
-            Constraint::Max(self.longest_mm_item_lens.0 + 16),
-            // Constraint::Min(self.longest_mm_item_lens.0 + 1),
+            Constraint::Length(id_width + 1),
+            Constraint::Max(self.longest_mm_item_lens[1] + 6),
+            Constraint::Max(self.longest_mm_item_lens[2] + 5),
+            Constraint::Max(self.longest_mm_item_lens[0] + 9),
+            Constraint::Max(self.longest_mm_item_lens[0] + 5),
+            Constraint::Max(self.longest_mm_item_lens[0] + 4),
+            Constraint::Max(self.longest_mm_item_lens[0] + 4),
+            Constraint::Max(self.longest_mm_item_lens[0] + 4),
+            Constraint::Max(self.longest_mm_item_lens[0] + 7),
+            Constraint::Max(self.longest_mm_item_lens[0] + 8),
+            Constraint::Max(self.longest_mm_item_lens[0] + 9),
+            Constraint::Max(self.longest_mm_item_lens[0] + 8),
+            Constraint::Max(self.longest_mm_item_lens[0] + 9),
+            Constraint::Max(self.longest_mm_item_lens[0] + 9),
+            Constraint::Max(self.longest_mm_item_lens[0] + 16),
+            // Constraint::Min(self.longest_mm_item_lens[0] + 1),
             Constraint::Length(16),
-            Constraint::Length(3),
-            Constraint::Length(3),
-            Constraint::Length(3),
         ];
 
         let width_dex = [
             // + 1 is for padding.
-            Constraint::Length(self.longest_mm_item_lens.0 + 1),
-            Constraint::Max(self.longest_mm_item_lens.1 + 6),
-            Constraint::Max(self.longest_mm_item_lens.2 + 5),
-            Constraint::Max(self.longest_mm_item_lens.0 + 3),
-            Constraint::Max(self.longest_mm_item_lens.0 + 2),
-            Constraint::Max(self.longest_mm_item_lens.0 + 7),
-            Constraint::Max(self.longest_mm_item_lens.0 + 10),
-            Constraint::Max(self.longest_mm_item_lens.0 + 5),
-            Constraint::Max(self.longest_mm_item_lens.0 + 4),
-            Constraint::Max(self.longest_mm_item_lens.0 + 7),
-            Constraint::Max(self.longest_mm_item_lens.0 + 8),
-            Constraint::Max(self.longest_mm_item_lens.0 + 8),
-            Constraint::Max(self.longest_mm_item_lens.0 + 8),
-            Constraint::Max(self.longest_mm_item_lens.0 + 9),
-            Constraint::Max(self.longest_mm_item_lens.0 + 2),
-            // Constraint::Min(self.longest_mm_item_lens.0 + 1),
-            Constraint::Length(16),
-            Constraint::Length(16),
+            Constraint::Length(id_width + 1),
+            Constraint::Max(self.longest_mm_item_lens[1] + 6),
+            Constraint::Max(self.longest_mm_item_lens[2] + 5),
+            Constraint::Max(self.longest_mm_item_lens[0] + 3),
+            Constraint::Max(self.longest_mm_item_lens[0] + 2),
+            Constraint::Max(self.longest_mm_item_lens[0] + 7),
+            Constraint::Max(self.longest_mm_item_lens[0] + 10),
+            Constraint::Max(self.longest_mm_item_lens[0] + 5),
+            Constraint::Max(self.longest_mm_item_lens[0] + 4),
+            Constraint::Max(self.longest_mm_item_lens[0] + 7),
+            Constraint::Max(self.longest_mm_item_lens[0] + 8),
+            Constraint::Max(self.longest_mm_item_lens[0] + 8),
+            Constraint::Max(self.longest_mm_item_lens[0] + 8),
+            Constraint::Max(self.longest_mm_item_lens[0] + 9),
+            Constraint::Max(self.longest_mm_item_lens[0] + 2),
+            // Constraint::Min(self.longest_mm_item_lens[0] + 1),
             Constraint::Length(16),
             Constraint::Length(16),
         ];
 
         let _width = match self.working_data {
-            WorkingData::Mm => width_mm,
-            WorkingData::Dex => width_dex,
+            WorkingData::Mm => width_mm.to_vec(),
+            WorkingData::Dex => width_dex.to_vec(),
         };
 
         let t = Table::new(rows, _width)
@@ -1721,6 +2269,65 @@ impl App {
         frame.render_stateful_widget(t, area, &mut self.state);
     }
 
+    fn task_rows<T: TaskRow>(&self, items: &[&T]) -> Vec<Row<'static>> {
+        let table = self.working_data.table();
+        let quick_filter = &self.view().quick_filter;
+        let changes = &self.view().changes;
+        let changed_style = Style::new().fg(Color::Black).bg(Color::Yellow);
+        let matched_style = Style::new()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        items
+            .iter()
+            .enumerate()
+            .map(|(i, data)| {
+                let color = match i % 2 {
+                    0 => self.colors.normal_row_color,
+                    _ => self.colors.alt_row_color,
+                };
+                let mut style = Style::new().fg(self.colors.row_fg).bg(color);
+                let mut cells: Vec<Text> = data
+                    .cells()
+                    .into_iter()
+                    .map(|content| {
+                        let positions = fuzzy::find(quick_filter, &content).unwrap_or_default();
+                        let line = fuzzy::highlight(&content, &positions, matched_style);
+                        Text::from(vec![Line::default(), line, Line::default()])
+                    })
+                    .collect();
+
+                // rows another process touched stand out until the highlight fades
+                let changed = changes.changed_columns(data.id());
+                if changes.is_added(data.id()) {
+                    cells[0].lines[2] = Line::from("新增");
+                    style = style.fg(Color::LightGreen);
+                } else if !changed.is_empty() {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+
+                if let Some(label) = self.actions.row_label(table, data.id()) {
+                    cells[0].lines[2] = Line::from(format!("{} {}", self.actions.spinner(), label));
+                    style = style.fg(Color::Yellow);
+                }
+
+                cells
+                    .into_iter()
+                    .enumerate()
+                    .map(|(column, text)| {
+                        let cell = Cell::from(text);
+                        if changed.contains(&column) {
+                            cell.style(changed_style)
+                        } else {
+                            cell
+                        }
+                    })
+                    .collect::<Row>()
+                    .style(style)
+                    .height(ROW_HEIGHT)
+            })
+            .collect()
+    }
+
     fn render_scrollbar(&mut self, frame: &mut Frame, area: Rect) {
         frame.render_stateful_widget(
             Scrollbar::default()
@@ -1736,7 +2343,56 @@ impl App {
     }
 
     fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        let info_footer = Paragraph::new(Text::from_iter(INFO_TEXT))
+        let (page, total) = self.current_page();
+        let first_row = if total == 0 { 0 } else { page.offset + 1 };
+        let last_row = page.offset
+            + match self.working_data {
+                WorkingData::Mm => self.mm_items.len(),
+                WorkingData::Dex => self.dex_items.len(),
+            } as u64;
+        let mut page_text = format!(
+            "page {}/{} | rows {}-{} of {} | (PgUp) previous page | (PgDn) next page",
+            page.number(),
+            page.count(total),
+            first_row,
+            last_row,
+            total
+        );
+        let quick_filter = &self.view().quick_filter;
+        if self.input_mode == InputMode::QuickFilter || !quick_filter.is_empty() {
+            let (cursor, keys) = if self.input_mode == InputMode::QuickFilter {
+                ("▏", "(Enter) keep | (Esc) clear")
+            } else {
+                ("", "(/) change")
+            };
+            page_text.push_str(&format!(
+                " | quick filter /{}{} ({} shown) | {}",
+                quick_filter,
+                cursor,
+                self.row_count(),
+                keys
+            ));
+        }
+
+        let view = self.view();
+        let mut page_line = Line::from(page_text);
+        page_line.push_span(Span::raw(format!(
+            " | last refreshed {}s ago",
+            view.refreshed_at.elapsed().as_secs()
+        )));
+        if self.refresh_paused {
+            page_line.push_span(Span::raw(" (paused)"));
+        }
+        if view.refresh_failed {
+            page_line.push_span(Span::styled(
+                " | last fetch failed",
+                Style::new().fg(Color::Red),
+            ));
+        }
+
+        let mut text = Text::from_iter(INFO_TEXT);
+        text.push_line(page_line);
+        let info_footer = Paragraph::new(text)
             .style(
                 Style::new()
                     .fg(self.colors.row_fg)
@@ -1757,18 +2413,24 @@ impl App {
             FocusArea::Upper => {
                 if item_index == self.header_item_selected_index {
                     // return Color::Rgb(60, 60, 60);
-                    return Color::Gray;
+                    Color::Gray
                 } else {
-                    return Color::Rgb(0, 0, 0);
+                    Color::Rgb(0, 0, 0)
                 }
             }
         }
     }
 
     fn render_search_input(&self, frame: &mut Frame, area: Rect) {
-        let search_input = Paragraph::new(self.search_word.as_str())
+        let search_input = Paragraph::new(self.view().search_word.as_str())
             .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
+                InputMode::Normal
+                | InputMode::Form
+                | InputMode::Confirm
+                | InputMode::Presets
+                | InputMode::QuickFilter
+                | InputMode::Environments
+                | InputMode::Conflict => Style::default(),
                 InputMode::Editing => {
                     // Style::default().fg(Color::Yellow)
                     match self.focus_area {
@@ -1778,9 +2440,18 @@ impl App {
                 }
             })
             .bg(self.get_herder_bg_color(0))
-            .block(Block::bordered().title("Search"));
+            .block(Block::bordered().title("Search: word | column=value | column~text"));
 
         frame.render_widget(search_input, area);
+
+        // the parse error hangs under the box, over the top of the table
+        if let Some(error) = &self.search_error {
+            #[allow(clippy::cast_possible_truncation)]
+            let width = (error.width() as u16 + 2).min(frame.area().width - area.x);
+            let error_area = Rect::new(area.x, area.y + area.height, width, 1);
+            frame.render_widget(Clear, error_area);
+            frame.render_widget(Paragraph::new(format!(" {} ", error)).red(), error_area);
+        }
     }
 
     fn render_header_button1(&self, frame: &mut Frame, area: Rect) {
@@ -1794,315 +2465,26 @@ impl App {
             .padding(Padding::new(1, 1, 0, 0));
         frame.render_widget(button_text_1.block(button_area), area);
     }
-}
-
-fn constraint_mm_len_calculator(
-    items: &[MmVolumeTask],
-) -> (
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-) {
-    let id_len = items
-        .iter()
-        .map(MmVolumeTask::id)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let launch_id_len = items
-        .iter()
-        .map(MmVolumeTask::launch_id)
-        // .flat_map(str::lines)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let token_add_len = items
-        .iter()
-        .map(MmVolumeTask::token_add)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let target_volume_len = items
-        .iter()
-        .map(MmVolumeTask::target_volume)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let do_status_len = items
-        .iter()
-        .map(MmVolumeTask::do_status)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let use_wallet_type_len = items
-        .iter()
-        .map(MmVolumeTask::use_wallet_type)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let remark_len = items
-        .iter()
-        .map(MmVolumeTask::remark)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_rate_len = items
-        .iter()
-        .map(MmVolumeTask::buy_rate)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_per_low_len = items
-        .iter()
-        .map(MmVolumeTask::buy_per_low)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_per_high_len = items
-        .iter()
-        .map(MmVolumeTask::buy_per_high)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let sell_percent_len = items
-        .iter()
-        .map(MmVolumeTask::sell_percent)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let frequent_low_len = items
-        .iter()
-        .map(MmVolumeTask::frequent_low)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let frequent_high_len = items
-        .iter()
-        .map(MmVolumeTask::frequent_high)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let real_sol_len = items
-        .iter()
-        .map(MmVolumeTask::real_sol)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let create_time_len = items
-        .iter()
-        .map(MmVolumeTask::create_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let update_time_len = items
-        .iter()
-        .map(MmVolumeTask::update_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let col1_len = 0;
-    let col2_len = 0;
-    let col3_len = 0;
-
-    #[allow(clippy::cast_possible_truncation)]
-    (
-        id_len as u16,
-        launch_id_len as u16,
-        token_add_len as u16,
-        target_volume_len as u16,
-        do_status_len as u16,
-        use_wallet_type_len as u16,
-        remark_len as u16,
-        buy_rate_len as u16,
-        buy_per_low_len as u16,
-        buy_per_high_len as u16,
-        sell_percent_len as u16,
-        frequent_low_len as u16,
-        frequent_high_len as u16,
-        real_sol_len as u16,
-        create_time_len as u16,
-        update_time_len as u16,
-        col1_len as u16,
-        col2_len as u16,
-        col3_len as u16,
-    )
-}
 
-fn constraint_dex_len_calculator(
-    items: &[DexVolumeTask],
-) -> (
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-    u16,
-) {
-    let id_len = items
-        .iter()
-        .map(DexVolumeTask::id)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let pool_id_len = items
-        .iter()
-        .map(DexVolumeTask::pool_id)
-        .flat_map(str::lines)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let token_add_len = items
-        .iter()
-        .map(DexVolumeTask::token_add)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let mm_type_len = items
-        .iter()
-        .map(DexVolumeTask::mm_type)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let remark_len = items
-        .iter()
-        .map(DexVolumeTask::remark)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let target_price_len = items
-        .iter()
-        .map(DexVolumeTask::target_price)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let stop_price_per_len = items
-        .iter()
-        .map(DexVolumeTask::stop_price_per)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let do_status_len = items
-        .iter()
-        .map(DexVolumeTask::do_status)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_rate_len = items
-        .iter()
-        .map(DexVolumeTask::buy_rate)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_per_low_len = items
-        .iter()
-        .map(DexVolumeTask::buy_per_low)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let buy_per_high_len = items
-        .iter()
-        .map(DexVolumeTask::buy_per_high)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let sell_percent_len = items
-        .iter()
-        .map(DexVolumeTask::sell_percent)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let frequent_low_len = items
-        .iter()
-        .map(DexVolumeTask::frequent_low)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let frequent_high_len = items
-        .iter()
-        .map(DexVolumeTask::frequent_high)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let bsdiff_len = items
-        .iter()
-        .map(DexVolumeTask::bsdiff)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let create_time_len = items
-        .iter()
-        .map(DexVolumeTask::create_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let update_time_len = items
-        .iter()
-        .map(DexVolumeTask::update_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let copy_len = items
-        .iter()
-        .map(DexVolumeTask::update_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
-    let del_len = items
-        .iter()
-        .map(DexVolumeTask::update_time)
-        .map(UnicodeWidthStr::width)
-        .max()
-        .unwrap_or(0);
+    fn render_preset_button(&self, frame: &mut Frame, area: Rect) {
+        let text = self.view().active_preset.as_deref().unwrap_or("筛选预设 ▾");
+        let button = Paragraph::new(text.white()).block(
+            Block::bordered()
+                .bg(self.get_herder_bg_color(3))
+                .padding(Padding::new(1, 1, 0, 0)),
+        );
+        frame.render_widget(button, area);
+    }
 
-    #[allow(clippy::cast_possible_truncation)]
-    (
-        id_len as u16,
-        pool_id_len as u16,
-        token_add_len as u16,
-        mm_type_len as u16,
-        remark_len as u16,
-        target_price_len as u16,
-        stop_price_per_len as u16,
-        do_status_len as u16,
-        buy_rate_len as u16,
-        buy_per_low_len as u16,
-        buy_per_high_len as u16,
-        sell_percent_len as u16,
-        frequent_low_len as u16,
-        frequent_high_len as u16,
-        bsdiff_len as u16,
-        create_time_len as u16,
-        update_time_len as u16,
-        copy_len as u16,
-        del_len as u16,
-    )
+    /// The connected environment, in its badge colors whatever has focus.
+    fn render_env_button(&self, frame: &mut Frame, area: Rect) {
+        let mut style = badge_style(self.production);
+        if self.focus_area == FocusArea::Upper && self.header_item_selected_index == 2 {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let button = Paragraph::new(format!("{} ▾", self.env))
+            .style(style)
+            .block(Block::bordered().padding(Padding::new(1, 1, 0, 0)));
+        frame.render_widget(button, area);
+    }
 }
@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+
+use ratatui::{
+    layout::{Position, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::Block,
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::schema::TaskTable;
+use crate::error::AppError;
+use crate::render_dropdown;
+
+/// Kept in the same directory as the config file.
+const FILE_NAME: &str = "presets.toml";
+
+/// A search of one view, saved under a name.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Preset {
+    pub name: String,
+    pub table: TaskTable,
+    /// Text for the search box, in the filter syntax.
+    pub filter: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PresetFile {
+    presets: Vec<Preset>,
+}
+
+impl Default for PresetFile {
+    fn default() -> Self {
+        Self {
+            presets: vec![
+                Preset {
+                    name: "running DEX tasks".to_string(),
+                    table: TaskTable::Dex,
+                    filter: "do_status=1".to_string(),
+                },
+                Preset {
+                    name: "tasks missing remark".to_string(),
+                    table: TaskTable::Mm,
+                    filter: "remark=null".to_string(),
+                },
+            ],
+        }
+    }
+}
+
+/// The saved searches of both views.
+pub struct Presets {
+    /// Unset when the file could not be read, so it is never written over.
+    path: Option<PathBuf>,
+    presets: Vec<Preset>,
+}
+
+impl Presets {
+    /// Read the presets file next to `config_path`, creating it with examples if missing.
+    pub fn load(config_path: &Path) -> Result<Self, AppError> {
+        let path = config_path.with_file_name(FILE_NAME);
+        let file: PresetFile = confy::load_path(&path).map_err(AppError::Presets)?;
+        Ok(Self {
+            path: Some(path),
+            presets: file.presets,
+        })
+    }
+
+    /// No presets, and no saving, after the file failed to load.
+    pub fn unavailable() -> Self {
+        Self {
+            path: None,
+            presets: vec![],
+        }
+    }
+
+    pub fn for_table(&self, table: TaskTable) -> Vec<Preset> {
+        self.presets
+            .iter()
+            .filter(|preset| preset.table == table)
+            .cloned()
+            .collect()
+    }
+
+    pub fn can_save(&self) -> bool {
+        self.path.is_some()
+    }
+
+    /// Add `preset`, replacing one with the same name in its view, and write the file.
+    pub fn save(&mut self, preset: Preset) -> Result<(), AppError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        self.presets
+            .retain(|saved| saved.table != preset.table || saved.name != preset.name);
+        self.presets.push(preset);
+        let file = PresetFile {
+            presets: self.presets.clone(),
+        };
+        confy::store_path(path, file).map_err(AppError::Presets)
+    }
+}
+
+/// What was picked in the preset dropdown.
+pub enum MenuChoice {
+    /// Drop the search of the view.
+    Clear,
+    Apply(Preset),
+    /// Save the current search under this name.
+    Save(String),
+}
+
+/// Dropdown under the preset button, listing the presets of one view.
+pub struct PresetMenu {
+    presets: Vec<Preset>,
+    can_save: bool,
+    selected: usize,
+    /// Name typed for the current search, once saving was picked.
+    naming: Option<String>,
+}
+
+impl PresetMenu {
+    pub fn new(presets: &Presets, table: TaskTable) -> Self {
+        Self {
+            presets: presets.for_table(table),
+            can_save: presets.can_save(),
+            selected: 0,
+            naming: None,
+        }
+    }
+
+    /// The clear entry, the presets, then the save entry.
+    fn len(&self) -> usize {
+        self.presets.len() + 1 + usize::from(self.can_save)
+    }
+
+    pub fn is_naming(&self) -> bool {
+        self.naming.is_some()
+    }
+
+    pub fn next(&mut self) {
+        self.selected = (self.selected + 1) % self.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.selected = (self.selected + self.len() - 1) % self.len();
+    }
+
+    pub fn enter_char(&mut self, new_char: char) {
+        if let Some(name) = &mut self.naming {
+            name.push(new_char);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(name) = &mut self.naming {
+            name.pop();
+        }
+    }
+
+    /// Leave the name prompt, returning true when there was none to leave.
+    pub fn cancel(&mut self) -> bool {
+        self.naming.take().is_none()
+    }
+
+    /// The entry under the cursor; the save entry asks for a name first.
+    pub fn choose(&mut self) -> Option<MenuChoice> {
+        if let Some(name) = &self.naming {
+            let name = name.trim();
+            return (!name.is_empty()).then(|| MenuChoice::Save(name.to_string()));
+        }
+
+        match self.selected {
+            0 => Some(MenuChoice::Clear),
+            index if index <= self.presets.len() => {
+                Some(MenuChoice::Apply(self.presets[index - 1].clone()))
+            }
+            _ => {
+                self.naming = Some(String::new());
+                None
+            }
+        }
+    }
+
+    /// Draw the dropdown hanging from `anchor`.
+    pub fn render(&self, frame: &mut Frame, anchor: Rect) {
+        let mut lines = vec![Line::from("清除筛选")];
+        for preset in &self.presets {
+            lines.push(Line::from(vec![
+                Span::raw(format!("{}  ", preset.name)),
+                Span::styled(preset.filter.as_str(), Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+        if self.can_save {
+            lines.push(Line::from("+ 保存当前搜索"));
+        }
+        lines[self.selected] = lines[self.selected].clone().yellow();
+
+        let prompt = "名称: ";
+        if let Some(name) = &self.naming {
+            lines.push(Line::from(format!("{}{}", prompt, name)).yellow());
+        }
+
+        let block = Block::bordered()
+            .title("筛选预设")
+            .title_bottom("(Enter) 选择 | (Esc) 取消");
+        #[allow(clippy::cast_possible_truncation)]
+        let last = lines.len() as u16 - 1;
+        let inner = render_dropdown(frame, anchor, lines, block);
+        #[allow(clippy::cast_possible_truncation)]
+        if let Some(name) = &self.naming {
+            frame.set_cursor_position(Position::new(
+                inner.x + (prompt.width() + name.width()) as u16,
+                inner.y + last,
+            ));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+use crate::db::dbutils::TaskPage;
+use crate::db::error::DbError;
+use crate::db::filter::Filter;
+
+/// Sent by a refresh loop after every attempt.
+pub enum Refresh<T> {
+    Loaded(TaskPage<T>),
+    Failed { error: DbError, retry_in: Duration },
+}
+
+/// Delay before retrying a failed refresh, doubling on every failure in a row.
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Backoff {
+    const FIRST: Duration = Duration::from_secs(1);
+    const MAX: Duration = Duration::from_secs(60);
+
+    pub fn new() -> Self {
+        Self { next: Self::FIRST }
+    }
+
+    /// Delay to wait after a failure.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = (self.next * 2).min(Self::MAX);
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = Self::FIRST;
+    }
+}
+
+/// Highest id a refresh loop last loaded, so the next load can count the rows inserted since.
+#[derive(Default)]
+pub struct Newest {
+    /// With the database and filter it was read under.
+    seen: Option<(String, Filter, u64)>,
+}
+
+impl Newest {
+    /// The id to count inserted rows from, unless the database or filter changed since.
+    pub fn since(&self, env: &str, filter: &Filter) -> Option<u64> {
+        self.seen
+            .as_ref()
+            .filter(|(seen_env, seen_filter, _)| seen_env == env && seen_filter == filter)
+            .map(|(_, _, id)| *id)
+    }
+
+    pub fn saw(&mut self, env: &str, filter: &Filter, newest: Option<u64>) {
+        self.seen = newest.map(|id| (env.to_string(), filter.clone(), id));
+    }
+}
+
+/// Lets the UI run a refresh loop early, or hold it until told otherwise.
+#[derive(Default)]
+pub struct Trigger {
+    wake: Notify,
+    paused: AtomicBool,
+}
+
+impl Trigger {
+    /// End the current wait, so the loop fetches now.
+    pub fn refresh_now(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Hold the loop between fetches; resuming fetches at once.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+        if !paused {
+            self.refresh_now();
+        }
+    }
+
+    /// Wait `delay` before the next fetch, or until `refresh_now` while paused.
+    pub async fn wait(&self, delay: Duration) {
+        if self.paused.load(Ordering::Relaxed) {
+            return self.wake.notified().await;
+        }
+
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = self.wake.notified() => {}
+        }
+    }
+}
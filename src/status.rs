@@ -0,0 +1,124 @@
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::error::DbError;
+use crate::db::schema::TaskTable;
+
+const TOAST_TIME: Duration = Duration::from_secs(5);
+const MAX_TOASTS: usize = 4;
+
+struct Toast {
+    message: String,
+    error: bool,
+    shown: Instant,
+}
+
+/// A refresh loop that is failing and waiting to try again.
+struct RefreshFailure {
+    table: TaskTable,
+    error: String,
+    retry_at: Instant,
+}
+
+/// Connection state for the status bar, plus short-lived toast messages.
+#[derive(Default)]
+pub struct Status {
+    failures: Vec<RefreshFailure>,
+    toasts: Vec<Toast>,
+}
+
+impl Status {
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(message.into(), false);
+    }
+
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(message.into(), true);
+    }
+
+    fn push(&mut self, message: String, error: bool) {
+        self.toasts.push(Toast {
+            message,
+            error,
+            shown: Instant::now(),
+        });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    pub fn refresh_failed(&mut self, table: TaskTable, error: &DbError, retry_in: Duration) {
+        self.refresh_succeeded(table);
+        self.failures.push(RefreshFailure {
+            table,
+            error: error.to_string(),
+            retry_at: Instant::now() + retry_in,
+        });
+    }
+
+    pub fn refresh_succeeded(&mut self, table: TaskTable) {
+        self.failures.retain(|failure| failure.table != table);
+    }
+
+    /// One line: the first failing refresh, or that the database is reachable.
+    pub fn render_bar(&self, frame: &mut Frame, area: Rect) {
+        let line = match self.failures.first() {
+            Some(failure) => {
+                let retry_in = failure.retry_at.saturating_duration_since(Instant::now());
+                Line::from(vec![
+                    Span::styled("● ", Style::default().fg(Color::Red)),
+                    Span::raw(format!(
+                        "{} refresh failed: {} (retrying in {}s)",
+                        failure.table.schema().table,
+                        failure.error,
+                        retry_in.as_secs()
+                    )),
+                ])
+            }
+            None => Line::from(vec![
+                Span::styled("● ", Style::default().fg(Color::Green)),
+                Span::raw("connected"),
+            ]),
+        };
+        frame.render_widget(Paragraph::new(line), area);
+    }
+
+    /// Stack the live toasts in the bottom-right corner of `area`.
+    pub fn render_toasts(&mut self, frame: &mut Frame, area: Rect) {
+        self.toasts
+            .retain(|toast| toast.shown.elapsed() < TOAST_TIME);
+
+        let mut bottom = area.bottom();
+        for toast in self.toasts.iter().rev() {
+            if bottom < area.y + 3 {
+                break;
+            }
+            #[allow(clippy::cast_possible_truncation)]
+            let width = (toast.message.width() as u16 + 4).min(area.width);
+            let [toast_area] = Layout::horizontal([Constraint::Length(width)])
+                .flex(Flex::End)
+                .areas(Rect::new(area.x, bottom - 3, area.width, 3));
+            bottom -= 3;
+
+            let color = if toast.error {
+                Color::Red
+            } else {
+                Color::Green
+            };
+            frame.render_widget(Clear, toast_area);
+            frame.render_widget(
+                Paragraph::new(toast.message.as_str().white())
+                    .block(Block::bordered().border_style(Style::default().fg(color))),
+                toast_area,
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use ratatui::widgets::{ScrollbarState, TableState};
+
+use crate::db::dbutils::{Page, Sort};
+use crate::db::filter::Filter;
+use crate::diff::Changes;
+use crate::refresh::Trigger;
+
+/// What a view's refresh task needs to fetch the page shown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub filter: Filter,
+    /// Column the rows are ordered by, by id when unset.
+    pub sort: Option<Sort>,
+    pub page: Page,
+}
+
+/// Everything one view keeps while the other is shown, so switching back restores it.
+pub struct View {
+    query: Query,
+    /// The copy of `query` read by the refresh task of this view.
+    shared: Arc<Mutex<Query>>,
+    /// Wakes or holds the refresh task of this view.
+    trigger: Arc<Trigger>,
+    /// When rows of the view were last loaded.
+    pub refreshed_at: Instant,
+    /// Whether the last background refresh failed.
+    pub refresh_failed: bool,
+    /// Query the loaded rows came from, to tell a refresh of them from another page;
+    /// unset while the rows are from another database.
+    pub shown: Option<Query>,
+    /// Rows the query matches across all pages.
+    pub total: u64,
+    /// Highest id the query matched when the rows were loaded.
+    pub newest: Option<u64>,
+    /// Rows recent refreshes changed or added.
+    pub changes: Changes,
+    /// Text of the search box, as last typed in this view.
+    pub search_word: String,
+    /// Preset whose filter the view shows, until the search changes.
+    pub active_preset: Option<String>,
+    /// Text typed after `/`, narrowing the loaded rows without a query; empty when off.
+    pub quick_filter: String,
+    /// Selection and scroll of the table, parked here while the other view is shown.
+    pub table_state: TableState,
+    pub scroll_state: ScrollbarState,
+}
+
+impl View {
+    pub fn new(page: Page, total: u64, newest: Option<u64>) -> Self {
+        let query = Query {
+            filter: Filter::default(),
+            sort: None,
+            page,
+        };
+        Self {
+            shared: Arc::new(Mutex::new(query.clone())),
+            trigger: Arc::new(Trigger::default()),
+            refreshed_at: Instant::now(),
+            refresh_failed: false,
+            shown: Some(query.clone()),
+            query,
+            total,
+            newest,
+            changes: Changes::default(),
+            search_word: String::new(),
+            active_preset: None,
+            quick_filter: String::new(),
+            table_state: TableState::default().with_selected(0),
+            scroll_state: ScrollbarState::default(),
+        }
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Handle for the refresh task, which sees every change made here.
+    pub fn shared(&self) -> Arc<Mutex<Query>> {
+        Arc::clone(&self.shared)
+    }
+
+    /// Handle for the refresh task, to wait on between fetches.
+    pub fn trigger(&self) -> Arc<Trigger> {
+        Arc::clone(&self.trigger)
+    }
+
+    /// Have the refresh task fetch the page now instead of at its next interval.
+    pub fn refresh_now(&self) {
+        self.trigger.refresh_now();
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.trigger.set_paused(paused);
+    }
+
+    /// Whether rows loaded for `query` are a refresh of the rows shown, not another page or search.
+    pub fn is_refresh(&self, query: &Query) -> bool {
+        self.shown.as_ref() == Some(query) && *query == self.query
+    }
+
+    /// Stop comparing fresh rows with the loaded ones, which came from another database.
+    pub fn forget_rows(&mut self) {
+        self.shown = None;
+        self.changes = Changes::default();
+    }
+
+    pub fn set_page(&mut self, page: Page) {
+        self.query.page = page;
+        self.publish();
+    }
+
+    pub fn set_sort(&mut self, sort: Option<Sort>) {
+        self.query.sort = sort;
+        self.publish();
+    }
+
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.query.filter = filter;
+        self.publish();
+    }
+
+    fn publish(&self) {
+        *self.shared.lock().unwrap() = self.query.clone();
+    }
+}